@@ -1,11 +1,11 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::error::{AppError, AppResult};
 use crate::input::{keyboard, mouse};
 use crate::screen::capture::capture_screenshot;
 use crate::shell::executor;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum AgentAction {
     Screenshot,
@@ -105,6 +105,27 @@ impl AgentAction {
             Self::ClickElement { id } => format!("Clicking element [{}]", id),
         }
     }
+
+    /// Stable snake_case name, used as the key for per-action limits.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Screenshot => "screenshot",
+            Self::MouseMove { .. } => "mouse_move",
+            Self::LeftClick { .. } => "left_click",
+            Self::RightClick { .. } => "right_click",
+            Self::DoubleClick { .. } => "double_click",
+            Self::Type { .. } => "type",
+            Self::Key { .. } => "key",
+            Self::Scroll { .. } => "scroll",
+            Self::Wait { .. } => "wait",
+            Self::Drag { .. } => "drag",
+            Self::BashCommand { .. } => "bash_command",
+            Self::TextEditorView { .. } => "text_editor_view",
+            Self::TextEditorCreate { .. } => "text_editor_create",
+            Self::TextEditorReplace { .. } => "text_editor_replace",
+            Self::ClickElement { .. } => "click_element",
+        }
+    }
 }
 
 /// Parse a computer tool call from the AI response
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::state::Settings;
+
+/// What to do with an action of a given kind before executing it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalPolicy {
+    Auto,
+    Ask,
+    Deny,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApprovalDecision {
+    pub approved: bool,
+    /// Optional note from the user, fed back to the model on rejection.
+    pub reason: Option<String>,
+}
+
+/// An `ask` action waiting on `approve_action` / `reject_action`.
+#[derive(Debug)]
+pub struct PendingApproval {
+    pub id: String,
+    tx: oneshot::Sender<ApprovalDecision>,
+}
+
+impl PendingApproval {
+    pub fn new(id: String) -> (Self, oneshot::Receiver<ApprovalDecision>) {
+        let (tx, rx) = oneshot::channel();
+        (Self { id, tx }, rx)
+    }
+
+    pub fn resolve(self, decision: ApprovalDecision) {
+        // The loop may have timed out or been cancelled in the meantime
+        let _ = self.tx.send(decision);
+    }
+}
+
+/// Policies applied when `Settings` has no entry for an action kind: anything that
+/// runs shell commands or writes files asks first.
+pub fn default_approval_policies() -> HashMap<String, ApprovalPolicy> {
+    ["bash_command", "text_editor_create", "text_editor_replace"]
+        .into_iter()
+        .map(|kind| (kind.to_string(), ApprovalPolicy::Ask))
+        .collect()
+}
+
+pub fn policy_for(settings: &Settings, kind: &str) -> ApprovalPolicy {
+    settings
+        .approval_policies
+        .get(kind)
+        .copied()
+        .unwrap_or(ApprovalPolicy::Auto)
+}
@@ -0,0 +1,178 @@
+use crate::ai::provider::AiProvider;
+use crate::ai::retry::{with_retry, RetryNotice, RetryPolicy};
+use crate::ai::types::{ContentBlock, Message, Usage};
+use crate::error::{AppError, AppResult};
+
+/// Rough size of a screenshot in tokens; providers bill images by pixel area, and at
+/// the display sizes used here that lands around this figure.
+const IMAGE_TOKENS: u64 = 1_500;
+
+/// Longest text kept from a single block when writing the history out for the
+/// summary request.
+const MAX_BLOCK_CHARS: usize = 2_000;
+
+/// Fewest tokens a compaction has to save for another one later in the run to be
+/// worth its summary request.
+const MIN_GAIN_TOKENS: u64 = 2_000;
+
+/// Starts the progress note on the task message, so a later compaction can find and
+/// replace it.
+const SUMMARY_HEADER: &str =
+    "Progress so far (earlier steps of this run were condensed into this note):";
+
+const SUMMARY_PROMPT: &str = "You condense the history of a computer-use agent so it can carry on with a shorter context. Write a progress note for the agent covering: what has been done so far, the current state of the screen and open applications, facts it found that it still needs (names, values, paths, URLs), approaches that failed and should not be retried, and what remains to do. Be specific and concise, in plain text, under 400 words. Do not continue the task yourself.";
+
+/// Estimated token count of a message: about four characters per token for text,
+/// a flat figure per image.
+pub fn estimate_tokens(message: &Message) -> u64 {
+    message
+        .content
+        .iter()
+        .map(|block| match block {
+            ContentBlock::Text { text } => text.len() as u64 / 4,
+            ContentBlock::Image { .. } => IMAGE_TOKENS,
+            ContentBlock::ToolUse { name, input, .. } => {
+                (name.len() + input.to_string().len()) as u64 / 4
+            }
+            ContentBlock::ToolResult { content, .. } => content.len() as u64 / 4,
+            ContentBlock::Thinking { thinking, .. } => thinking.len() as u64 / 4,
+            ContentBlock::RedactedThinking { data } => data.len() as u64 / 4,
+        })
+        .sum()
+}
+
+pub fn total_tokens(messages: &[Message]) -> u64 {
+    messages.iter().map(estimate_tokens).sum()
+}
+
+/// Result of a compaction, for logging and usage accounting.
+pub struct Compaction {
+    pub summarized: usize,
+    pub tokens_before: u64,
+    pub tokens_after: u64,
+    pub usage: Usage,
+}
+
+impl Compaction {
+    /// Whether compacting again later could help: this pass got the history under
+    /// `threshold` and saved a useful amount. Otherwise the recent turns alone are too
+    /// large, and every further pass would just pay to summarize the summary again.
+    pub fn worth_repeating(&self, threshold: u64) -> bool {
+        self.tokens_after <= threshold
+            && self.tokens_before.saturating_sub(self.tokens_after) >= MIN_GAIN_TOKENS
+    }
+}
+
+/// Index where the verbatim tail starts. The tail begins with an assistant turn, so
+/// every tool result it holds answers a tool call that is also in the tail, and the
+/// summarized range always ends with the results of its last tool calls.
+fn split_point(messages: &[Message], keep_recent: usize) -> Option<usize> {
+    let mut cut = messages.len().saturating_sub(keep_recent).max(1);
+    while cut < messages.len() && messages[cut].role != "assistant" {
+        cut += 1;
+    }
+    // Nothing between the task message and the tail means nothing to condense
+    (cut > 1 && cut < messages.len()).then_some(cut)
+}
+
+fn clip(text: &str) -> String {
+    match text.char_indices().nth(MAX_BLOCK_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Plain-text rendering of the history for the summary request. Sending it as text
+/// rather than as turns avoids replaying tool calls and screenshots to the model.
+fn render(messages: &[Message]) -> String {
+    let mut out = String::new();
+    for message in messages {
+        for block in &message.content {
+            let line = match block {
+                ContentBlock::Text { text } => format!("[{}] {}", message.role, clip(text)),
+                ContentBlock::Image { .. } => format!("[{}] (screenshot)", message.role),
+                ContentBlock::ToolUse { name, input, .. } => {
+                    format!("[tool call] {} {}", name, clip(&input.to_string()))
+                }
+                ContentBlock::ToolResult {
+                    content, is_error, ..
+                } => {
+                    let label = if *is_error == Some(true) {
+                        "tool error"
+                    } else {
+                        "tool result"
+                    };
+                    format!("[{}] {}", label, clip(content))
+                }
+                ContentBlock::Thinking { .. } | ContentBlock::RedactedThinking { .. } => continue,
+            };
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Replace everything between the task message and the most recent
+/// `keep_recent` messages with a progress note written by the model. The note is
+/// added to the task message (replacing the note from any earlier compaction), which
+/// keeps the conversation alternating and starting with the original task.
+/// Returns `None` when the history is too short to condense.
+pub async fn compact(
+    messages: &mut Vec<Message>,
+    provider: &dyn AiProvider,
+    keep_recent: usize,
+    retry_policy: &RetryPolicy,
+    on_retry: impl FnMut(&RetryNotice),
+) -> AppResult<Option<Compaction>> {
+    let Some(cut) = split_point(messages, keep_recent) else {
+        return Ok(None);
+    };
+    let tokens_before = total_tokens(messages);
+
+    let request = [Message {
+        role: "user".to_string(),
+        content: vec![ContentBlock::Text {
+            text: format!(
+                "Agent history so far:\n\n{}\nWrite the progress note now.",
+                render(&messages[..cut])
+            ),
+        }],
+    }];
+    let response = with_retry(
+        retry_policy,
+        || provider.send(SUMMARY_PROMPT, &request, &[], &|_| {}),
+        on_retry,
+    )
+    .await?;
+    let summary = response
+        .content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::Text { text } => Some(text.trim()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if summary.is_empty() {
+        return Err(AppError::Agent(
+            "Compaction returned an empty summary".into(),
+        ));
+    }
+
+    messages.drain(1..cut);
+    let task = &mut messages[0].content;
+    task.retain(
+        |block| !matches!(block, ContentBlock::Text { text } if text.starts_with(SUMMARY_HEADER)),
+    );
+    task.push(ContentBlock::Text {
+        text: format!("{}\n\n{}", SUMMARY_HEADER, summary),
+    });
+
+    Ok(Some(Compaction {
+        summarized: cut - 1,
+        tokens_before,
+        tokens_after: total_tokens(messages),
+        usage: response.usage,
+    }))
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{AppError, AppResult};
+
+/// How a run ended, as reported by the model through `task_complete`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Success,
+    Failure,
+    Blocked,
+    /// The model stopped without calling `task_complete` and the fallback ended the run.
+    Unknown,
+}
+
+/// Outcome of a finished run, kept on `AgentState` and sent with `task-finished`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskResult {
+    pub status: TaskStatus,
+    pub summary: String,
+    /// Optional structured output, e.g. values the task asked the agent to find.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+}
+
+impl TaskResult {
+    /// Parse the input of a `task_complete` tool call.
+    pub fn from_tool_input(input: &Value) -> AppResult<Self> {
+        let status = match input["status"].as_str() {
+            Some("success") => TaskStatus::Success,
+            Some("failure") => TaskStatus::Failure,
+            Some("blocked") => TaskStatus::Blocked,
+            other => {
+                return Err(AppError::Agent(format!(
+                    "task_complete status must be success, failure or blocked, got {}",
+                    other.unwrap_or("nothing")
+                )))
+            }
+        };
+        let summary = input["summary"].as_str().unwrap_or("").trim().to_string();
+        if summary.is_empty() {
+            return Err(AppError::Agent("task_complete requires a summary".into()));
+        }
+        // Providers that can't declare free-form objects (Gemini) send `result` as
+        // a JSON string
+        let result = match input.get("result") {
+            Some(Value::String(text)) => Some(
+                serde_json::from_str::<Value>(text)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| Value::String(text.clone())),
+            ),
+            other => other.filter(|v| !v.is_null()).cloned(),
+        };
+        Ok(Self {
+            status,
+            summary,
+            result,
+        })
+    }
+}
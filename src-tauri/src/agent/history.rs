@@ -1,41 +1,181 @@
+use std::io::Cursor;
+
+use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+
 use crate::ai::types::{ContentBlock, Message};
+use crate::state::Settings;
+
+/// Most UI elements named in the placeholder left behind by an evicted screenshot.
+const PLACEHOLDER_ELEMENTS: usize = 20;
+
+/// Start of the text block placed right before a screenshot in a tool result turn.
+/// Tool results have to come first in the turn, so this is what ties each
+/// screenshot to the tool call it answers.
+const SCREENSHOT_LABEL: &str = "Screenshot from tool call ";
+
+const THUMBNAIL_QUALITY: u8 = 60;
+
+/// How many screenshots `trim_history` keeps at each tier, newest first: full
+/// resolution, then small grayscale thumbnails. Older ones become text placeholders.
+pub struct ScreenshotTiers {
+    full: usize,
+    thumbnails: usize,
+    thumbnail_width: u32,
+}
+
+impl ScreenshotTiers {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            full: settings.screenshot_history_full as usize,
+            thumbnails: settings.screenshot_history_thumbnails as usize,
+            thumbnail_width: settings.screenshot_thumbnail_width.max(1),
+        }
+    }
+}
 
-const MAX_SCREENSHOT_MESSAGES: usize = 5;
+/// The label that goes right before a screenshot taken by tool call `tool_use_id`.
+pub fn screenshot_label(tool_use_id: &str) -> ContentBlock {
+    ContentBlock::Text {
+        text: format!("{}{}:", SCREENSHOT_LABEL, tool_use_id),
+    }
+}
+
+/// Id of the tool call that took the screenshot at `index` in `content`: the one named
+/// by the label before it, or, in turns recorded before labels existed, the tool
+/// result right before it.
+pub fn screenshot_owner(content: &[ContentBlock], index: usize) -> Option<&str> {
+    match content.get(index.checked_sub(1)?)? {
+        ContentBlock::Text { text } => text.strip_prefix(SCREENSHOT_LABEL)?.strip_suffix(':'),
+        ContentBlock::ToolResult { tool_use_id, .. } => Some(tool_use_id),
+        _ => None,
+    }
+}
 
-/// Manage conversation history with a sliding window for screenshots.
-/// Keeps the first message (user instruction) and trims old screenshot-heavy messages.
-pub fn trim_history(messages: &mut Vec<Message>) {
+/// Manage conversation history with tiered screenshots. The first message (user
+/// instruction) is never touched; beyond it the newest screenshots stay as they
+/// are, the next ones are shrunk to thumbnails, and the rest are replaced by a
+/// note listing the UI elements that were visible.
+pub fn trim_history(messages: &mut [Message], tiers: &ScreenshotTiers) {
     if messages.len() <= 3 {
         return;
     }
 
-    // Count messages containing screenshots
-    let screenshot_indices: Vec<usize> = messages
+    let mut seen = 0;
+    for msg in messages.iter_mut().skip(1).rev() {
+        for i in (0..msg.content.len()).rev() {
+            let ContentBlock::Image { source } = &mut msg.content[i] else {
+                continue;
+            };
+            seen += 1;
+            if seen <= tiers.full {
+                continue;
+            }
+            if seen <= tiers.full + tiers.thumbnails {
+                if let Some(thumbnail) = thumbnail(&source.data, tiers.thumbnail_width) {
+                    source.data = thumbnail;
+                    source.media_type = "image/jpeg".to_string();
+                }
+                continue;
+            }
+            // The screenshot's tool result holds the accessibility tree it came with
+            let tree = screenshot_owner(&msg.content, i).and_then(|owner| {
+                msg.content.iter().find_map(|block| match block {
+                    ContentBlock::ToolResult {
+                        tool_use_id,
+                        content,
+                        ..
+                    } if tool_use_id == owner => Some(content.as_str()),
+                    _ => None,
+                })
+            });
+            msg.content[i] = ContentBlock::Text {
+                text: placeholder(tree),
+            };
+        }
+    }
+}
+
+/// Base64 grayscale JPEG of a base64 screenshot scaled down to `width`, or `None` if
+/// it is already that small (i.e. a thumbnail from an earlier pass) or can't be read.
+fn thumbnail(base64: &str, width: u32) -> Option<String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(base64)
+        .ok()?;
+    let (current_width, _) = image::ImageReader::new(Cursor::new(&bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+    if current_width <= width {
+        return None;
+    }
+
+    let small = image::load_from_memory(&bytes)
+        .ok()?
+        .resize(width, u32::MAX, FilterType::Triangle)
+        .grayscale();
+    let mut buf = Cursor::new(Vec::new());
+    let encoder = JpegEncoder::new_with_quality(&mut buf, THUMBNAIL_QUALITY);
+    small.write_with_encoder(encoder).ok()?;
+    Some(base64::engine::general_purpose::STANDARD.encode(buf.into_inner()))
+}
+
+/// Text left in place of an evicted screenshot, naming the elements from its
+/// accessibility tree (lines like `[12] Button "OK"`).
+fn placeholder(tree: Option<&str>) -> String {
+    let elements: Vec<&str> = tree
+        .unwrap_or("")
+        .lines()
+        .filter(|line| line.starts_with('[') && !line.ends_with("\"\""))
+        .filter_map(|line| line.split_once("] ").map(|(_, element)| element))
+        .collect();
+    if elements.is_empty() {
+        return "[Earlier screenshot removed to save context]".to_string();
+    }
+
+    let named: Vec<&str> = elements
+        .iter()
+        .take(PLACEHOLDER_ELEMENTS)
+        .copied()
+        .collect();
+    format!(
+        "[Earlier screenshot removed to save context. {} UI elements were visible, including: {}]",
+        elements.len(),
+        named.join(", ")
+    )
+}
+
+/// Index of the last message that future calls to `trim_history` will never modify.
+///
+/// Trimming only rewrites images, and always from the oldest screenshot first, so
+/// everything before the oldest message still holding an image is a stable prefix
+/// that can safely be marked for prompt caching.
+pub fn stable_prefix_end(messages: &[Message]) -> Option<usize> {
+    let first_image = messages
         .iter()
         .enumerate()
-        .filter(|(i, msg)| {
-            *i > 0
-                && msg.content.iter().any(|block| match block {
-                    ContentBlock::Image { .. } => true,
-                    _ => false,
-                })
+        .skip(1)
+        .find(|(_, msg)| {
+            msg.content
+                .iter()
+                .any(|block| matches!(block, ContentBlock::Image { .. }))
         })
         .map(|(i, _)| i)
-        .collect();
+        .unwrap_or(messages.len());
+    first_image.checked_sub(1)
+}
 
-    if screenshot_indices.len() > MAX_SCREENSHOT_MESSAGES {
-        let to_remove = screenshot_indices.len() - MAX_SCREENSHOT_MESSAGES;
-        // Remove the oldest screenshot messages (but keep the first user message)
-        let indices_to_remove: Vec<usize> = screenshot_indices[..to_remove].to_vec();
-        // Remove from back to front to preserve indices
-        for &idx in indices_to_remove.iter().rev() {
-            if idx > 0 && idx < messages.len() {
-                // Strip image content from this message instead of removing it entirely
-                // This preserves tool results / text context
-                messages[idx].content.retain(|block| {
-                    !matches!(block, ContentBlock::Image { .. })
-                });
-            }
-        }
+/// Drop reasoning blocks before handing the conversation to a different provider.
+/// Their signatures are only valid for the model that produced them.
+pub fn strip_reasoning(messages: &mut [Message]) {
+    for msg in messages.iter_mut() {
+        msg.content.retain(|block| {
+            !matches!(
+                block,
+                ContentBlock::Thinking { .. } | ContentBlock::RedactedThinking { .. }
+            )
+        });
     }
 }
@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+use crate::agent::history::screenshot_label;
+use crate::ai::types::{ContentBlock, Message};
+
+/// Check the conversation against the rules providers enforce and fix what breaks
+/// them, returning a description of each repair:
+///
+/// - turns with no content are dropped,
+/// - consecutive turns from the same role are merged,
+/// - tool calls without a result get a synthetic error result in the next user turn,
+/// - tool results that answer no call in the previous turn become plain text,
+/// - tool results come before anything else (screenshots, notes) in their turn,
+///   with screenshots labelled so they stay tied to their tool call.
+pub fn repair_conversation(messages: &mut Vec<Message>) -> Vec<String> {
+    let mut repairs = Vec::new();
+
+    let mut position = 0;
+    messages.retain(|msg| {
+        position += 1;
+        if msg.content.is_empty() {
+            repairs.push(format!(
+                "dropped empty {} turn at position {}",
+                msg.role,
+                position - 1
+            ));
+            return false;
+        }
+        true
+    });
+
+    messages.dedup_by(|next, prev| {
+        if next.role != prev.role {
+            return false;
+        }
+        repairs.push(format!("merged consecutive {} turns", prev.role));
+        prev.content.append(&mut next.content);
+        true
+    });
+
+    let mut i = 0;
+    while i < messages.len() {
+        if messages[i].role != "assistant" {
+            i += 1;
+            continue;
+        }
+        let calls: Vec<String> = messages[i]
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::ToolUse { id, .. } => Some(id.clone()),
+                _ => None,
+            })
+            .collect();
+
+        if messages.get(i + 1).map(|m| m.role.as_str()) != Some("user") {
+            if calls.is_empty() {
+                i += 1;
+                continue;
+            }
+            messages.insert(
+                i + 1,
+                Message {
+                    role: "user".to_string(),
+                    content: Vec::new(),
+                },
+            );
+        }
+
+        let turn = &mut messages[i + 1].content;
+        let call_ids: HashSet<&str> = calls.iter().map(String::as_str).collect();
+        for block in turn.iter_mut() {
+            if let ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                ..
+            } = block
+            {
+                if !call_ids.contains(tool_use_id.as_str()) {
+                    repairs.push(format!(
+                        "turned result for unknown tool call {} into text",
+                        tool_use_id
+                    ));
+                    *block = ContentBlock::Text {
+                        text: format!("(Result of an earlier tool call) {}", content),
+                    };
+                }
+            }
+        }
+
+        let answered: HashSet<String> = turn
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::ToolResult { tool_use_id, .. } => Some(tool_use_id.clone()),
+                _ => None,
+            })
+            .collect();
+        let missing: Vec<ContentBlock> = calls
+            .iter()
+            .filter(|id| !answered.contains(*id))
+            .map(|id| {
+                repairs.push(format!("added missing result for tool call {}", id));
+                ContentBlock::ToolResult {
+                    tool_use_id: id.clone(),
+                    content: "Error: this tool call was not executed.".to_string(),
+                    is_error: Some(true),
+                }
+            })
+            .collect();
+        turn.splice(0..0, missing);
+
+        // Stable partition: results first, everything else after in its original order
+        let first_other = turn
+            .iter()
+            .position(|b| !matches!(b, ContentBlock::ToolResult { .. }));
+        let result_after_other = first_other.is_some_and(|start| {
+            turn[start..]
+                .iter()
+                .any(|b| matches!(b, ContentBlock::ToolResult { .. }))
+        });
+        if result_after_other {
+            // Label screenshots that sit right after their tool result (the layout of
+            // older runs) so they stay tied to it once the results move
+            let mut labelled = Vec::with_capacity(turn.len());
+            for block in std::mem::take(turn) {
+                if matches!(block, ContentBlock::Image { .. }) {
+                    if let Some(ContentBlock::ToolResult { tool_use_id, .. }) = labelled.last() {
+                        labelled.push(screenshot_label(tool_use_id));
+                    }
+                }
+                labelled.push(block);
+            }
+            let (results, others): (Vec<_>, Vec<_>) = labelled
+                .into_iter()
+                .partition(|b| matches!(b, ContentBlock::ToolResult { .. }));
+            turn.extend(results);
+            turn.extend(others);
+            repairs.push(format!(
+                "moved tool results ahead of other content at position {}",
+                i + 1
+            ));
+        }
+
+        i += 2;
+    }
+
+    repairs
+}
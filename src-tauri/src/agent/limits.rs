@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::state::Settings;
+
+/// Which run limit stopped the agent.
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitHit {
+    /// `iterations`, `duration`, `actions` or `budget`.
+    pub limit: String,
+    /// Action kind that hit its cap, for `actions` limits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    pub message: String,
+    /// Base64 JPEG of the last screenshot the agent saw, if any.
+    pub screenshot: Option<String>,
+}
+
+/// Limits for a single run; a zero in `Settings` disables the matching limit.
+pub struct RunLimits {
+    max_iterations: u32,
+    max_duration: Option<Duration>,
+    deadline: Option<Instant>,
+    max_actions: HashMap<String, u32>,
+    action_counts: HashMap<String, u32>,
+}
+
+impl RunLimits {
+    pub fn from_settings(settings: &Settings) -> Self {
+        let max_duration = (settings.max_run_duration_secs > 0)
+            .then(|| Duration::from_secs(settings.max_run_duration_secs));
+        Self {
+            max_iterations: settings.max_iterations,
+            max_duration,
+            deadline: max_duration.map(|d| Instant::now() + d),
+            max_actions: settings
+                .max_actions
+                .iter()
+                .filter(|(_, max)| **max > 0)
+                .map(|(kind, max)| (kind.clone(), *max))
+                .collect(),
+            action_counts: HashMap::new(),
+        }
+    }
+
+    pub fn check_iteration(&self, iteration: u32) -> Option<LimitHit> {
+        (self.max_iterations > 0 && iteration >= self.max_iterations).then(|| LimitHit {
+            limit: "iterations".to_string(),
+            action: None,
+            message: format!(
+                "Iteration limit reached: stopped after {} iterations.",
+                self.max_iterations
+            ),
+            screenshot: None,
+        })
+    }
+
+    pub fn check_deadline(&self) -> Option<LimitHit> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Some(self.duration_hit()),
+            _ => None,
+        }
+    }
+
+    /// Resolves once the wall-clock limit passes; never resolves without one.
+    pub async fn deadline_elapsed(&self) -> LimitHit {
+        match self.deadline {
+            Some(deadline) => {
+                tokio::time::sleep_until(deadline).await;
+                self.duration_hit()
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Push the wall-clock deadline back, e.g. by the time spent paused.
+    pub fn extend_deadline(&mut self, by: Duration) {
+        if let Some(deadline) = self.deadline.as_mut() {
+            *deadline += by;
+        }
+    }
+
+    /// Count an action about to run, returning a hit if it would exceed its cap.
+    pub fn record_action(&mut self, kind: &str) -> Option<LimitHit> {
+        let count = self.action_counts.entry(kind.to_string()).or_insert(0);
+        *count += 1;
+        let max = *self.max_actions.get(kind)?;
+        (*count > max).then(|| LimitHit {
+            limit: "actions".to_string(),
+            action: Some(kind.to_string()),
+            message: format!(
+                "Action limit reached: {} is capped at {} per run.",
+                kind, max
+            ),
+            screenshot: None,
+        })
+    }
+
+    fn duration_hit(&self) -> LimitHit {
+        let secs = self.max_duration.map(|d| d.as_secs()).unwrap_or(0);
+        LimitHit {
+            limit: "duration".to_string(),
+            action: None,
+            message: format!("Time limit reached: stopped after {} seconds.", secs),
+            screenshot: None,
+        }
+    }
+}
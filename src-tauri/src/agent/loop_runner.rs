@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use serde_json::json;
@@ -5,29 +7,28 @@ use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::{Mutex, RwLock};
 use tokio_util::sync::CancellationToken;
 
-use crate::agent::action::{execute_action, parse_computer_action, AgentAction};
-use crate::agent::history::trim_history;
+use crate::agent::action::{execute_action, parse_computer_action, ActionResult, AgentAction};
+use crate::agent::approval::{self, ApprovalDecision, ApprovalPolicy, PendingApproval};
+use crate::agent::compaction::{compact, total_tokens};
+use crate::agent::completion::{TaskResult, TaskStatus};
+use crate::agent::history::{screenshot_label, strip_reasoning, trim_history, ScreenshotTiers};
+use crate::agent::integrity::repair_conversation;
+use crate::agent::limits::{LimitHit, RunLimits};
+use crate::agent::pause::PauseToken;
+use crate::agent::prompt::build_system_prompt;
+use crate::agent::replay::{replay_run, ReplayOptions, ReplayOutcome};
+use crate::agent::stuck::StuckDetector;
 use crate::agent::tools::build_tool_definitions;
-use crate::ai::anthropic::AnthropicClient;
-use crate::ai::ollama::OllamaClient;
-use crate::ai::openai::OpenAiClient;
-use crate::ai::openrouter::OpenRouterClient;
+use crate::ai::provider::{AiProvider, ProviderRegistry, StreamEvent};
+use crate::ai::retry::{with_retry, RetryPolicy};
 use crate::ai::types::{AiResponse, ContentBlock, ImageSource, Message};
+use crate::ai::usage::{price_for, ModelPrice, RunUsage};
 use crate::error::AppResult;
-use crate::state::{AgentState, Settings};
-
-const SYSTEM_PROMPT: &str = r#"You are an AI agent running on Delegant that controls a computer to accomplish tasks. You can see the screen via screenshots and perform actions using the available tools.
-
-IMPORTANT GUIDELINES:
-- Always take a screenshot first to see the current state of the screen before acting.
-- Screenshots show the full screen. Coordinates are pixel positions from top-left (0,0).
-- Be precise with coordinates when clicking — aim for the center of buttons, links, and text fields.
-- Look carefully at the screenshot to identify clickable UI elements, menus, icons, and text.
-- After performing an action, take a screenshot to verify the result before proceeding.
-- If something doesn't work, try an alternative approach.
-- Use bash/shell commands when they are more efficient than GUI interactions.
-- When you believe the task is complete, say so clearly and stop using tools.
-- In your text responses, include a JSON snippet estimating remaining time: {"estimated_seconds": N} where N is your best estimate of seconds remaining to complete the task. Update this estimate as you progress."#;
+use crate::memory::tools::{
+    describe_memory_call, is_memory_tool, relevant_memories_note, run_memory_tool,
+};
+use crate::state::{AgentState, AgentStatus, AppState, FallbackProvider, Settings};
+use crate::storage::runs::{self, RunRecorder, RunStatus};
 
 fn show_cursor_overlay(app: &AppHandle) {
     if let Some(win) = app.get_webview_window("cursor-overlay") {
@@ -42,84 +43,714 @@ fn hide_cursor_overlay(app: &AppHandle) {
     }
 }
 
+/// Take a screenshot outside of a tool call, e.g. to show the model the screen after
+/// the user had control, and mirror it to the frontend.
+async fn fresh_screenshot(app: &AppHandle, settings: &Settings) -> Option<String> {
+    match execute_action(
+        &AgentAction::Screenshot,
+        settings.display_width,
+        settings.display_height,
+        settings.shell_timeout_secs,
+    )
+    .await
+    {
+        Ok(ActionResult {
+            base64: Some(base64),
+            ..
+        }) => {
+            let _ = app.emit(
+                "screenshot-updated",
+                json!({"base64": base64, "w": settings.display_width, "h": settings.display_height}),
+            );
+            Some(base64)
+        }
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("Failed to take screenshot: {}", e);
+            None
+        }
+    }
+}
+
+fn screenshot_block(base64: String) -> ContentBlock {
+    ContentBlock::Image {
+        source: ImageSource {
+            source_type: "base64".to_string(),
+            media_type: "image/jpeg".to_string(),
+            data: base64,
+        },
+    }
+}
+
+/// What the loop needs to pick up again after a pause.
+struct Resumed {
+    /// Note and fresh screenshot for the model, since the user may have changed the screen.
+    blocks: Vec<ContentBlock>,
+    screenshot: Option<String>,
+    paused_for: std::time::Duration,
+}
+
+/// Wait while the run is paused. Returns how long it was paused, or `None` if it
+/// wasn't paused or was cancelled while paused.
+pub(crate) async fn wait_while_paused(
+    pause_token: &PauseToken,
+    cancel_token: &CancellationToken,
+    agent_state: &Mutex<AgentState>,
+    app: &AppHandle,
+) -> Option<std::time::Duration> {
+    if !pause_token.is_paused() {
+        return None;
+    }
+
+    let paused_at = std::time::Instant::now();
+    agent_state.lock().await.status = AgentStatus::Paused;
+    let _ = app.emit("agent-status-changed", "paused");
+    hide_cursor_overlay(app);
+
+    tokio::select! {
+        _ = pause_token.resumed() => {}
+        _ = cancel_token.cancelled() => return None,
+    }
+
+    agent_state.lock().await.status = AgentStatus::Running;
+    let _ = app.emit("agent-status-changed", "running");
+    show_cursor_overlay(app);
+    Some(paused_at.elapsed())
+}
+
+/// Wait out a pause at a safe point. Returns `None` if the run wasn't paused or was
+/// cancelled while paused, so callers should check the cancel token afterwards.
+async fn pause_point(
+    pause_token: &PauseToken,
+    cancel_token: &CancellationToken,
+    agent_state: &Mutex<AgentState>,
+    app: &AppHandle,
+    settings: &Settings,
+) -> Option<Resumed> {
+    let paused_for = wait_while_paused(pause_token, cancel_token, agent_state, app).await?;
+
+    let mut blocks = vec![ContentBlock::Text {
+        text: "The run was paused and has now resumed. The user may have changed the screen in the meantime; this is its current state.".to_string(),
+    }];
+    let screenshot = fresh_screenshot(app, settings).await;
+    blocks.extend(screenshot.clone().map(screenshot_block));
+
+    Some(Resumed {
+        blocks,
+        screenshot,
+        paused_for,
+    })
+}
+
+/// Ask the user to approve an action and wait for their decision. Returns `None` if
+/// the run was cancelled while waiting; an unanswered request is rejected on timeout.
+pub(crate) async fn ask_approval(
+    action: &AgentAction,
+    agent_state: &Mutex<AgentState>,
+    app: &AppHandle,
+    cancel_token: &CancellationToken,
+    timeout_secs: u64,
+) -> Option<ApprovalDecision> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let (pending, rx) = PendingApproval::new(id.clone());
+    agent_state.lock().await.pending_approval = Some(pending);
+
+    hide_cursor_overlay(app);
+    let _ = app.emit(
+        "approval-requested",
+        json!({
+            "id": id,
+            "action_type": action.kind(),
+            "description": action.description(),
+            "details": action,
+            "timeout_secs": timeout_secs
+        }),
+    );
+
+    let decision = tokio::select! {
+        res = tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), rx) => match res {
+            Ok(Ok(decision)) => decision,
+            // Timed out, or the request was dropped without an answer
+            _ => ApprovalDecision {
+                approved: false,
+                reason: Some("No response from the user before the approval timed out.".to_string()),
+            },
+        },
+        _ = cancel_token.cancelled() => {
+            agent_state.lock().await.pending_approval = None;
+            return None;
+        }
+    };
+
+    agent_state.lock().await.pending_approval = None;
+    show_cursor_overlay(app);
+    let _ = app.emit(
+        "approval-resolved",
+        json!({ "id": id, "approved": decision.approved, "reason": decision.reason }),
+    );
+    Some(decision)
+}
+
+/// Add blocks to the trailing user turn, or start one if the model spoke last.
+fn append_user_blocks(messages: &mut Vec<Message>, blocks: Vec<ContentBlock>) {
+    match messages.last_mut() {
+        Some(last) if last.role == "user" => last.content.extend(blocks),
+        _ => messages.push(Message {
+            role: "user".to_string(),
+            content: blocks,
+        }),
+    }
+}
+
+/// After a reply that ended without usable tool calls because a call was malformed
+/// (`tool_error`) or the reply ran out of tokens (`max_tokens`), tell the model what
+/// went wrong. Without the note the next request would be the same one again.
+/// Returns whether a note was added.
+fn note_unusable_turn(messages: &mut Vec<Message>, stop_reason: &str, has_tool_use: bool) -> bool {
+    let text = match (stop_reason, has_tool_use) {
+        ("tool_error", false) => "Your last tool call was malformed and could not be run. Check the tool's parameters and call it again with valid arguments.",
+        ("max_tokens", false) => "Your last reply was cut off because it reached the output token limit, so no tool call came through. Keep your reasoning and text short and make the tool call right away.",
+        _ => return false,
+    };
+    log::warn!(
+        "Model turn ended with {}, asking it to try again",
+        stop_reason
+    );
+    append_user_blocks(
+        messages,
+        vec![ContentBlock::Text {
+            text: text.to_string(),
+        }],
+    );
+    true
+}
+
+/// Keep a turn the run is ending in the middle of: the model's whole reply and
+/// whatever came back from the tool calls that already ran. Calls left without a
+/// result are answered on resume (or by `repair_conversation`), so actions that did
+/// run stay in the transcript.
+fn record_cut_turn(
+    messages: &mut Vec<Message>,
+    recorder: &mut RunRecorder,
+    response: &AiResponse,
+    results: Vec<ContentBlock>,
+) {
+    messages.push(Message {
+        role: "assistant".to_string(),
+        content: response.content.clone(),
+    });
+    if !results.is_empty() {
+        messages.push(Message {
+            role: "user".to_string(),
+            content: results,
+        });
+    }
+    recorder.sync_messages(messages);
+}
+
+/// Tell the user when a cost budget is set but can't be enforced because `model` has
+/// no entry in the price table, so its requests cost nothing as far as the run knows.
+fn warn_if_unpriced(settings: &Settings, model: &str, price: Option<&ModelPrice>, app: &AppHandle) {
+    if settings.max_cost_per_task_usd <= 0.0 || price.is_some() {
+        return;
+    }
+    let text = format!(
+        "The cost budget can't be enforced: {} has no price set, so its requests aren't counted. Add it to model_prices or set a token budget instead.",
+        model
+    );
+    log::warn!("{}", text);
+    let _ = app.emit("agent-message", json!({ "text": text }));
+}
+
+/// Report an exceeded budget and describe it as the limit that ends the run.
+fn budget_limit(reason: String, run_usage: &RunUsage, app: &AppHandle) -> LimitHit {
+    let _ = app.emit(
+        "budget-exceeded",
+        json!({ "reason": reason, "usage": run_usage }),
+    );
+    LimitHit {
+        limit: "budget".to_string(),
+        action: None,
+        message: reason,
+        screenshot: None,
+    }
+}
+
+/// Record the run's outcome and tell the frontend.
+async fn finish_task(result: TaskResult, agent_state: &Mutex<AgentState>, app: &AppHandle) {
+    let _ = app.emit("agent-message", json!({ "text": result.summary }));
+    let _ = app.emit("task-finished", &result);
+    agent_state.lock().await.task_result = Some(result);
+}
+
+/// End the run on a tripped limit, recording it for `start_agent` to report.
+async fn stop_at_limit(
+    mut hit: LimitHit,
+    last_screenshot: Option<String>,
+    agent_state: &Mutex<AgentState>,
+    app: &AppHandle,
+) -> AppResult<()> {
+    log::warn!("{}", hit.message);
+    hit.screenshot = last_screenshot;
+    let _ = app.emit("agent-message", json!({ "text": hit.message }));
+    let _ = app.emit("limit-reached", &hit);
+    agent_state.lock().await.limit_reached = Some(hit);
+    hide_cursor_overlay(app);
+    Ok(())
+}
+
+/// Pop fallbacks until one the registry can build, returning it with its client.
+fn next_fallback(
+    fallbacks: &mut VecDeque<FallbackProvider>,
+    providers: &ProviderRegistry,
+    settings: &Settings,
+) -> Option<(FallbackProvider, Arc<dyn AiProvider>)> {
+    while let Some(candidate) = fallbacks.pop_front() {
+        let candidate_settings = Settings {
+            provider: candidate.provider.clone(),
+            model: candidate.model.clone(),
+            ..settings.clone()
+        };
+        match providers.create(&candidate.provider, &candidate_settings) {
+            Ok(client) => return Some((candidate, client)),
+            Err(e) => log::warn!("Skipping fallback {}: {}", candidate.provider, e),
+        }
+    }
+    None
+}
+
+/// Where a run's conversation comes from.
+pub enum RunStart {
+    /// A new run for the given task.
+    New(String),
+    /// Continue a stored run, by id.
+    Resume(String),
+    /// Re-execute a stored run's actions without the model.
+    Replay {
+        run_id: String,
+        options: ReplayOptions,
+    },
+}
+
 pub async fn run_agent_loop(
-    task: String,
+    start: RunStart,
     cancel_token: CancellationToken,
+    pause_token: PauseToken,
     settings: Arc<RwLock<Settings>>,
+    providers: Arc<ProviderRegistry>,
     agent_state: Arc<Mutex<AgentState>>,
     app: AppHandle,
 ) -> AppResult<()> {
     let s = settings.read().await.clone();
+    let runs_dir = runs::runs_dir(&app)?;
+
+    let start = match start {
+        RunStart::Replay { run_id, options } => {
+            let outcome = replay_run(
+                &run_id,
+                &options,
+                &s,
+                &cancel_token,
+                &pause_token,
+                &agent_state,
+                &app,
+            )
+            .await?;
+            let status = match outcome {
+                ReplayOutcome::Completed => "completed",
+                ReplayOutcome::Cancelled => "cancelled",
+                ReplayOutcome::Aborted => "diverged",
+                ReplayOutcome::HandOff(_) => "handed_off",
+            };
+            let _ = app.emit(
+                "replay-finished",
+                json!({ "run_id": run_id, "status": status }),
+            );
+            match outcome {
+                ReplayOutcome::HandOff(task) => RunStart::New(task),
+                _ => return Ok(()),
+            }
+        }
+        start => start,
+    };
+
+    let (mut recorder, mut messages) = match start {
+        RunStart::New(task) => {
+            let recorder = RunRecorder::create(&runs_dir, &task, &s)?;
+            // Initial user message with the task
+            let mut content = vec![ContentBlock::Text {
+                text: format!(
+                    "Task: {}\n\nThe screen resolution is {}x{} pixels. Coordinates are [x, y] from the top-left corner. Please start by taking a screenshot to see the current state of the screen.",
+                    task, s.display_width, s.display_height
+                ),
+            }];
+            if s.memory_enabled && s.memory_inject_count > 0 {
+                let store = app.state::<AppState>().memory.clone();
+                let store = store.lock().await;
+                if let Some(note) =
+                    relevant_memories_note(&store, &task, s.memory_inject_count as usize)
+                {
+                    content.push(ContentBlock::Text { text: note });
+                }
+            }
+            let messages = vec![Message {
+                role: "user".to_string(),
+                content,
+            }];
+            (recorder, messages)
+        }
+        RunStart::Resume(id) => {
+            let (mut recorder, mut messages) = RunRecorder::reopen(&runs_dir, &id)?;
+            // Tool calls from a turn that never got to run still need results
+            let mut blocks: Vec<ContentBlock> = match messages.last() {
+                Some(last) if last.role == "assistant" => last
+                    .content
+                    .iter()
+                    .filter_map(|block| match block {
+                        ContentBlock::ToolUse { id, .. } => Some(ContentBlock::ToolResult {
+                            tool_use_id: id.clone(),
+                            content: "Not executed: the run stopped before this action ran."
+                                .to_string(),
+                            is_error: Some(true),
+                        }),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            blocks.push(ContentBlock::Text {
+                text: "This run stopped and is now being resumed. The screen may have changed since; this is its current state. Continue the task from here.".to_string(),
+            });
+            blocks.extend(fresh_screenshot(&app, &s).await.map(screenshot_block));
+            recorder.append_user_turn(&mut messages, blocks);
+            trim_history(&mut messages, &ScreenshotTiers::from_settings(&s));
+            (recorder, messages)
+        }
+        RunStart::Replay { .. } => unreachable!("replays are resolved above"),
+    };
+    let mut agent = agent_state.lock().await;
+    // Once stopped, the next run may already own the shared state
+    if !cancel_token.is_cancelled() {
+        agent.run_id = Some(recorder.id().to_string());
+    }
+    drop(agent);
+
+    let result = drive(
+        &mut messages,
+        &mut recorder,
+        s,
+        cancel_token.clone(),
+        pause_token,
+        providers,
+        agent_state.clone(),
+        app,
+    )
+    .await;
 
-    let tools = build_tool_definitions(&s.provider, s.display_width, s.display_height);
+    recorder.sync_messages(&messages);
+    let agent = agent_state.lock().await;
+    let status = match &result {
+        Err(_) => RunStatus::Failed,
+        Ok(()) if agent.task_result.is_some() => RunStatus::Finished,
+        Ok(()) if agent.limit_reached.is_some() => RunStatus::LimitReached,
+        Ok(()) if cancel_token.is_cancelled() => RunStatus::Cancelled,
+        Ok(()) => RunStatus::Stopped,
+    };
+    recorder.finish(
+        status,
+        agent.task_result.clone(),
+        result.as_ref().err().map(|e| e.to_string()),
+    );
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn drive(
+    messages: &mut Vec<Message>,
+    recorder: &mut RunRecorder,
+    s: Settings,
+    cancel_token: CancellationToken,
+    pause_token: PauseToken,
+    providers: Arc<ProviderRegistry>,
+    agent_state: Arc<Mutex<AgentState>>,
+    app: AppHandle,
+) -> AppResult<()> {
+    // One client per provider so its connection pool is reused across iterations
+    let mut provider = providers.create(&s.provider, &s)?;
+    let mut current = FallbackProvider {
+        provider: s.provider.clone(),
+        model: s.model.clone(),
+    };
+    log::info!(
+        "Starting agent run with {} / {}",
+        provider.id(),
+        current.model
+    );
+    let mut tools = build_tool_definitions(
+        &provider.capabilities(),
+        s.display_width,
+        s.display_height,
+        s.memory_enabled,
+    );
+    let mut system_prompt = build_system_prompt(&s, &current.provider);
+    let mut fallbacks: VecDeque<FallbackProvider> = s
+        .fallback_providers
+        .iter()
+        .filter(|f| **f != current)
+        .cloned()
+        .collect();
+    let retry_policy = RetryPolicy::from_settings(&s);
+    let mut price = price_for(&s.model_prices, &current.model);
+    warn_if_unpriced(&s, &current.model, price.as_ref(), &app);
+    let mut run_usage = RunUsage::default();
 
     // Show cursor overlay
     show_cursor_overlay(&app);
 
-    // Initial user message with the task
-    let mut messages: Vec<Message> = vec![Message {
-        role: "user".to_string(),
-        content: vec![ContentBlock::Text {
-            text: format!(
-                "Task: {}\n\nThe screen resolution is {}x{} pixels. Coordinates are [x, y] from the top-left corner. Please start by taking a screenshot to see the current state of the screen.",
-                task, s.display_width, s.display_height
-            ),
-        }],
-    }];
-
+    let mut limits = RunLimits::from_settings(&s);
+    let mut stuck = StuckDetector::from_settings(&s);
+    let mut reminders_left = s.completion_reminders;
+    let mut compaction_enabled = true;
+    let screenshot_tiers = ScreenshotTiers::from_settings(&s);
+    let mut last_screenshot: Option<String> = None;
     let mut iteration: u32 = 0;
     let mut last_nodes: Option<Vec<crate::screen::a11y::A11yNode>> = None;
 
     loop {
+        // Safe point: between provider calls
+        if let Some(resumed) =
+            pause_point(&pause_token, &cancel_token, &agent_state, &app, &s).await
+        {
+            limits.extend_deadline(resumed.paused_for);
+            last_screenshot = resumed.screenshot.or(last_screenshot);
+            append_user_blocks(messages, resumed.blocks);
+        }
+
+        // Deliver follow-up messages sent while the last iteration ran
+        let queued = std::mem::take(&mut agent_state.lock().await.pending_messages);
+        if !queued.is_empty() {
+            let blocks = queued
+                .into_iter()
+                .map(|text| ContentBlock::Text {
+                    text: format!("Follow-up message from the user: {}", text),
+                })
+                .collect();
+            append_user_blocks(messages, blocks);
+        }
+
         if cancel_token.is_cancelled() {
             hide_cursor_overlay(&app);
             return Ok(());
         }
 
+        if let Some(hit) = limits
+            .check_iteration(iteration)
+            .or_else(|| limits.check_deadline())
+        {
+            return stop_at_limit(hit, last_screenshot, &agent_state, &app).await;
+        }
+
         // Update iteration
         {
             let mut agent = agent_state.lock().await;
             agent.iteration = iteration;
         }
 
-        // Send to AI
-        let _ = app.emit("agent-thinking", json!({"text": "Thinking..."}));
+        // Everything before this request is final, so it can go to disk
+        recorder.sync_messages(messages);
 
-        let response: AiResponse = tokio::select! {
-            res = async {
-                match s.provider.as_str() {
-                    "openai" => {
-                        let client = OpenAiClient::new(&s.openai_api_key, &s.model);
-                        client.send(SYSTEM_PROMPT, &messages, &tools).await
-                    }
-                    "openrouter" => {
-                        let client = OpenRouterClient::new(&s.openrouter_api_key, &s.model);
-                        client.send(SYSTEM_PROMPT, &messages, &tools).await
-                    }
-                    "ollama" => {
-                        let client =
-                            OllamaClient::new(&s.ollama_api_key, &s.model, &s.ollama_base_url);
-                        client.send(SYSTEM_PROMPT, &messages, &tools).await
+        // Condense older turns once the history grows too large
+        if compaction_enabled
+            && s.compaction_threshold_tokens > 0
+            && total_tokens(messages) > s.compaction_threshold_tokens
+        {
+            let _ = app.emit(
+                "agent-thinking",
+                json!({"text": "Condensing earlier steps..."}),
+            );
+            let keep_recent = s.compaction_keep_recent as usize;
+            let result = tokio::select! {
+                res = compact(
+                    messages,
+                    provider.as_ref(),
+                    keep_recent,
+                    &retry_policy,
+                    |notice| {
+                        let _ = app.emit("agent-retrying", notice);
+                    },
+                ) => res,
+                _ = cancel_token.cancelled() => {
+                    hide_cursor_overlay(&app);
+                    return Ok(());
+                }
+            };
+            match result {
+                Ok(Some(compaction)) => {
+                    log::info!(
+                        "Compacted {} messages: ~{} -> ~{} tokens",
+                        compaction.summarized,
+                        compaction.tokens_before,
+                        compaction.tokens_after
+                    );
+                    recorder.mark_rewritten(messages);
+                    run_usage.record(&compaction.usage, price.as_ref());
+                    agent_state.lock().await.usage = run_usage.clone();
+                    let _ = app.emit("usage-updated", &run_usage);
+                    let _ = app.emit(
+                        "context-compacted",
+                        json!({
+                            "summarized": compaction.summarized,
+                            "tokens_before": compaction.tokens_before,
+                            "tokens_after": compaction.tokens_after,
+                            "timestamp": chrono::Utc::now().to_rfc3339(),
+                            "iteration": iteration
+                        }),
+                    );
+                    if !compaction.worth_repeating(s.compaction_threshold_tokens) {
+                        log::info!(
+                            "Compaction can't shrink the history further, not retrying this run"
+                        );
+                        compaction_enabled = false;
                     }
-                    _ => {
-                        let client = AnthropicClient::new(&s.anthropic_api_key, &s.model);
-                        client.send(SYSTEM_PROMPT, &messages, &tools).await
+                    if let Some(reason) =
+                        run_usage.exceeded_budget(s.max_tokens_per_task, s.max_cost_per_task_usd)
+                    {
+                        let hit = budget_limit(reason, &run_usage, &app);
+                        return stop_at_limit(hit, last_screenshot, &agent_state, &app).await;
                     }
                 }
-            } => res?,
+                Ok(None) => {}
+                Err(e) => {
+                    // Carry on with the full history and leave it to the provider
+                    log::warn!("Context compaction failed, not retrying this run: {}", e);
+                    compaction_enabled = false;
+                }
+            }
+        }
+
+        // Fix anything in the history a provider would reject
+        let repairs = repair_conversation(messages);
+        if !repairs.is_empty() {
+            for repair in &repairs {
+                log::warn!("Repaired conversation: {}", repair);
+            }
+            recorder.mark_rewritten(messages);
+        }
+
+        // Send to AI
+        let _ = app.emit("agent-thinking", json!({"text": "Thinking..."}));
+
+        // Set once an attempt streams anything, so a retry can take it back
+        let streamed = AtomicBool::new(false);
+        let on_event = |event: StreamEvent| {
+            streamed.store(true, Ordering::Relaxed);
+            match event {
+                StreamEvent::TextDelta(text) => {
+                    let _ = app.emit("agent-message-delta", json!({"text": text}));
+                }
+                StreamEvent::ThinkingDelta(text) => {
+                    let _ = app.emit("agent-thinking", json!({"text": text, "delta": true}));
+                }
+            }
+        };
+
+        let send = with_retry(
+            &retry_policy,
+            || provider.send(&system_prompt, messages, &tools, &on_event),
+            |notice| {
+                // The next attempt streams its reply from the start
+                if streamed.swap(false, Ordering::Relaxed) {
+                    let _ = app.emit("agent-stream-reset", ());
+                }
+                let _ = app.emit("agent-retrying", notice);
+            },
+        );
+
+        let result = tokio::select! {
+            res = send => res,
+            hit = limits.deadline_elapsed() => {
+                return stop_at_limit(hit, last_screenshot, &agent_state, &app).await;
+            }
             _ = cancel_token.cancelled() => {
                 hide_cursor_overlay(&app);
                 return Ok(());
             }
         };
 
+        let response: AiResponse = match result {
+            Ok(response) => response,
+            Err(e) => {
+                let Some((next, client)) = next_fallback(&mut fallbacks, &providers, &s) else {
+                    return Err(e);
+                };
+                log::warn!(
+                    "{} / {} failed, switching to {} / {}: {}",
+                    current.provider,
+                    current.model,
+                    next.provider,
+                    next.model,
+                    e
+                );
+                let _ = app.emit(
+                    "provider-switched",
+                    json!({
+                        "from": current,
+                        "to": next,
+                        "reason": e.to_string(),
+                    }),
+                );
+                // The history is provider-neutral apart from signed reasoning
+                strip_reasoning(messages);
+                tools = build_tool_definitions(
+                    &client.capabilities(),
+                    s.display_width,
+                    s.display_height,
+                    s.memory_enabled,
+                );
+                system_prompt = build_system_prompt(&s, &next.provider);
+                price = price_for(&s.model_prices, &next.model);
+                warn_if_unpriced(&s, &next.model, price.as_ref(), &app);
+                provider = client;
+                current = next;
+                continue;
+            }
+        };
+
+        log::info!(
+            "Usage: {} input, {} output, {} cache read, {} cache write",
+            response.usage.input_tokens,
+            response.usage.output_tokens,
+            response.usage.cache_read_input_tokens,
+            response.usage.cache_creation_input_tokens
+        );
+        run_usage.record(&response.usage, price.as_ref());
+        agent_state.lock().await.usage = run_usage.clone();
+        let _ = app.emit("usage-updated", &run_usage);
+
+        if let Some(reason) =
+            run_usage.exceeded_budget(s.max_tokens_per_task, s.max_cost_per_task_usd)
+        {
+            record_cut_turn(messages, recorder, &response, Vec::new());
+            let hit = budget_limit(reason, &run_usage, &app);
+            return stop_at_limit(hit, last_screenshot, &agent_state, &app).await;
+        }
+
         // Process response
         let mut assistant_blocks: Vec<ContentBlock> = Vec::new();
         let mut tool_results: Vec<ContentBlock> = Vec::new();
+        // Screenshots and resume notes go after every tool result, so results stay
+        // first in the turn; each screenshot is labelled with its tool call
+        let mut screenshots: Vec<ContentBlock> = Vec::new();
+        let mut resume_notes: Vec<ContentBlock> = Vec::new();
         let mut has_tool_use = false;
+        let mut finished: Option<TaskResult> = None;
 
         for block in &response.content {
             match block {
+                // Kept in place so signed reasoning goes back to the provider unchanged
+                ContentBlock::Thinking { .. } | ContentBlock::RedactedThinking { .. } => {
+                    assistant_blocks.push(block.clone());
+                }
                 ContentBlock::Text { text } => {
                     assistant_blocks.push(block.clone());
                     let _ = app.emit("agent-message", json!({"text": text}));
@@ -144,6 +775,99 @@ pub async fn run_agent_loop(
                     has_tool_use = true;
                     assistant_blocks.push(block.clone());
 
+                    if finished.is_some() {
+                        tool_results.push(ContentBlock::ToolResult {
+                            tool_use_id: id.clone(),
+                            content: "Skipped: the task was already marked complete.".to_string(),
+                            is_error: Some(true),
+                        });
+                        continue;
+                    }
+
+                    if name.as_str() == "task_complete" {
+                        match TaskResult::from_tool_input(input) {
+                            Ok(result) => {
+                                tool_results.push(ContentBlock::ToolResult {
+                                    tool_use_id: id.clone(),
+                                    content: "Task finished.".to_string(),
+                                    is_error: None,
+                                });
+                                finished = Some(result);
+                            }
+                            Err(e) => tool_results.push(ContentBlock::ToolResult {
+                                tool_use_id: id.clone(),
+                                content: format!("Error: {}", e),
+                                is_error: Some(true),
+                            }),
+                        }
+                        continue;
+                    }
+
+                    if is_memory_tool(name) {
+                        let store = app.state::<AppState>().memory.clone();
+                        let result = run_memory_tool(
+                            name,
+                            input,
+                            &mut *store.lock().await,
+                            recorder.id(),
+                            &app,
+                        );
+                        let _ = app.emit(
+                            "action-executed",
+                            json!({
+                                "action_type": name,
+                                "description": describe_memory_call(name, input),
+                                "timestamp": chrono::Utc::now().to_rfc3339(),
+                                "iteration": iteration
+                            }),
+                        );
+                        tool_results.push(match result {
+                            Ok(content) => ContentBlock::ToolResult {
+                                tool_use_id: id.clone(),
+                                content,
+                                is_error: None,
+                            },
+                            Err(e) => ContentBlock::ToolResult {
+                                tool_use_id: id.clone(),
+                                content: format!("Error: {}", e),
+                                is_error: Some(true),
+                            },
+                        });
+                        continue;
+                    }
+
+                    // Handle get_element_position early to prevent it from going to execute_action
+                    if name.as_str() == "get_element_position" {
+                        let elem_id = input["id"].as_i64().unwrap_or(-1) as i32;
+                        let mut found = false;
+
+                        if let Some(nodes) = &last_nodes {
+                            if let Some(node) = nodes.iter().find(|n| n.id == elem_id) {
+                                let (x, y, r, b) = node.rect;
+                                let cx = x + (r - x) / 2;
+                                let cy = y + (b - y) / 2;
+                                tool_results.push(ContentBlock::ToolResult {
+                                    tool_use_id: id.clone(),
+                                    content: format!("Element ID {} position:\nBounding Box: [left: {}, top: {}, right: {}, bottom: {}]\nCenter: [cx: {}, cy: {}]", elem_id, x, y, r, b, cx, cy),
+                                    is_error: None,
+                                });
+                                found = true;
+                            }
+                        }
+
+                        if !found {
+                            tool_results.push(ContentBlock::ToolResult {
+                                tool_use_id: id.clone(),
+                                content: format!("Error: Element [{}] not found in the current accessibility tree. Take a screenshot first.", elem_id),
+                                is_error: Some(true),
+                            });
+                        }
+
+                        // Delay and continue loop, bypassing standard execute_action
+                        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                        continue;
+                    }
+
                     // Parse and execute the action
                     let mut action = match name.as_str() {
                         "computer" => {
@@ -185,61 +909,117 @@ pub async fn run_agent_loop(
                         }
                     };
 
-                    // Handle get_element_position early to prevent it from going to execute_action
-                    if name.as_str() == "get_element_position" {
-                        let elem_id = input["id"].as_i64().unwrap_or(-1) as i32;
+                    // Translate click_element by ID into LeftClick coordinates
+                    if let AgentAction::ClickElement { id: elem_id } = action {
                         let mut found = false;
-
                         if let Some(nodes) = &last_nodes {
                             if let Some(node) = nodes.iter().find(|n| n.id == elem_id) {
                                 let (x, y, r, b) = node.rect;
+                                // Calculate center of bounding box
                                 let cx = x + (r - x) / 2;
                                 let cy = y + (b - y) / 2;
-                                tool_results.push(ContentBlock::ToolResult {
-                                    tool_use_id: id.clone(),
-                                    content: format!("Element ID {} position:\nBounding Box: [left: {}, top: {}, right: {}, bottom: {}]\nCenter: [cx: {}, cy: {}]", elem_id, x, y, r, b, cx, cy),
-                                    is_error: None,
-                                });
+                                action = AgentAction::LeftClick { x: cx, y: cy };
                                 found = true;
                             }
                         }
-
                         if !found {
                             tool_results.push(ContentBlock::ToolResult {
                                 tool_use_id: id.clone(),
                                 content: format!("Error: Element [{}] not found in the current accessibility tree. Take a screenshot first.", elem_id),
                                 is_error: Some(true),
                             });
+                            continue;
                         }
+                    }
 
-                        // Delay and continue loop, bypassing standard execute_action
-                        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-                        continue;
+                    // Safe point: between actions (a drag always runs to completion)
+                    if let Some(resumed) =
+                        pause_point(&pause_token, &cancel_token, &agent_state, &app, &s).await
+                    {
+                        limits.extend_deadline(resumed.paused_for);
+                        last_screenshot = resumed.screenshot.or(last_screenshot);
+                        resume_notes.extend(resumed.blocks);
+                    }
+                    if cancel_token.is_cancelled() {
+                        record_cut_turn(
+                            messages,
+                            recorder,
+                            &response,
+                            [tool_results, screenshots, resume_notes].concat(),
+                        );
+                        hide_cursor_overlay(&app);
+                        return Ok(());
                     }
 
-                    // Translate click_element by ID into LeftClick coordinates
-                    if let AgentAction::ClickElement { id: elem_id } = action {
-                        let mut found = false;
-                        if let Some(nodes) = &last_nodes {
-                            if let Some(node) = nodes.iter().find(|n| n.id == elem_id) {
-                                let (x, y, r, b) = node.rect;
-                                // Calculate center of bounding box
-                                let cx = x + (r - x) / 2;
-                                let cy = y + (b - y) / 2;
-                                action = AgentAction::LeftClick { x: cx, y: cy };
-                                found = true;
-                            }
-                        }
-                        if !found {
+                    match approval::policy_for(&s, action.kind()) {
+                        ApprovalPolicy::Auto => {}
+                        ApprovalPolicy::Deny => {
                             tool_results.push(ContentBlock::ToolResult {
                                 tool_use_id: id.clone(),
-                                content: format!("Error: Element [{}] not found in the current accessibility tree. Take a screenshot first.", elem_id),
+                                content: format!(
+                                    "Not executed: {} actions are blocked by the user's approval policy. Find another way or explain what you need.",
+                                    action.kind()
+                                ),
                                 is_error: Some(true),
                             });
                             continue;
                         }
+                        ApprovalPolicy::Ask => {
+                            let asked_at = std::time::Instant::now();
+                            let decision = ask_approval(
+                                &action,
+                                &agent_state,
+                                &app,
+                                &cancel_token,
+                                s.approval_timeout_secs,
+                            )
+                            .await;
+                            // Waiting on the user doesn't count against the run duration
+                            limits.extend_deadline(asked_at.elapsed());
+                            let Some(decision) = decision else {
+                                record_cut_turn(
+                                    messages,
+                                    recorder,
+                                    &response,
+                                    [tool_results, screenshots, resume_notes].concat(),
+                                );
+                                hide_cursor_overlay(&app);
+                                return Ok(());
+                            };
+                            if !decision.approved {
+                                let mut content = "The user rejected this action.".to_string();
+                                if let Some(reason) =
+                                    decision.reason.filter(|r| !r.trim().is_empty())
+                                {
+                                    content.push_str(&format!(" Reason: {}", reason.trim()));
+                                }
+                                tool_results.push(ContentBlock::ToolResult {
+                                    tool_use_id: id.clone(),
+                                    content,
+                                    is_error: Some(true),
+                                });
+                                continue;
+                            }
+                        }
+                    }
+
+                    if let Some(hit) = limits
+                        .record_action(action.kind())
+                        .or_else(|| limits.check_deadline())
+                    {
+                        record_cut_turn(
+                            messages,
+                            recorder,
+                            &response,
+                            [tool_results, screenshots, resume_notes].concat(),
+                        );
+                        return stop_at_limit(hit, last_screenshot, &agent_state, &app).await;
                     }
 
+                    let mut detection = stuck.record_action(&action);
+
+                    recorder.record_action(iteration, id, &action);
+
                     // Emit action executed
                     let _ = app.emit(
                         "action-executed",
@@ -273,14 +1053,25 @@ pub async fn run_agent_loop(
                     }
 
                     // Execute
-                    let shell_timeout = settings.read().await.shell_timeout_secs;
-                    let max_w = settings.read().await.display_width;
-                    let max_h = settings.read().await.display_height;
+                    let shell_timeout = s.shell_timeout_secs;
+                    let max_w = s.display_width;
+                    let max_h = s.display_height;
 
                     let action_result = tokio::select! {
                         res = execute_action(&action, max_w, max_h, shell_timeout) => res,
                         _ = cancel_token.cancelled() => {
                             // If cancelled during execution, bail out immediately
+                            tool_results.push(ContentBlock::ToolResult {
+                                tool_use_id: id.clone(),
+                                content: "Interrupted: the run was stopped while this action was running.".to_string(),
+                                is_error: Some(true),
+                            });
+                            record_cut_turn(
+                                messages,
+                                recorder,
+                                &response,
+                                [tool_results, screenshots, resume_notes].concat(),
+                            );
                             hide_cursor_overlay(&app);
                             return Ok(());
                         }
@@ -303,6 +1094,9 @@ pub async fn run_agent_loop(
                                 }
 
                                 if let Some(base64) = result.base64 {
+                                    last_screenshot = Some(base64.clone());
+                                    let screen = stuck.record_screenshot(&base64);
+                                    detection = detection.or(screen);
                                     let _ = app.emit(
                                         "screenshot-updated",
                                         json!({"base64": base64, "w": max_w, "h": max_h}),
@@ -312,13 +1106,8 @@ pub async fn run_agent_loop(
                                         content: dom_text,
                                         is_error: None,
                                     });
-                                    tool_results.push(ContentBlock::Image {
-                                        source: ImageSource {
-                                            source_type: "base64".to_string(),
-                                            media_type: "image/jpeg".to_string(),
-                                            data: base64,
-                                        },
-                                    });
+                                    screenshots.push(screenshot_label(id));
+                                    screenshots.push(screenshot_block(base64));
                                 }
                             } else {
                                 tool_results.push(ContentBlock::ToolResult {
@@ -337,6 +1126,35 @@ pub async fn run_agent_loop(
                         }
                     }
 
+                    // Nudge the model out of a stuck loop, pausing for the user if nudges stop working
+                    if let Some(detection) = detection {
+                        log::warn!(
+                            "Stuck loop detected ({}): {}",
+                            detection.kind,
+                            detection.message
+                        );
+                        let _ = app.emit("stuck-detected", &detection);
+                        let result = tool_results.iter_mut().rev().find_map(|block| match block {
+                            ContentBlock::ToolResult {
+                                tool_use_id,
+                                content,
+                                ..
+                            } if tool_use_id == id => Some(content),
+                            _ => None,
+                        });
+                        if let Some(content) = result {
+                            content.push_str("\n\n");
+                            content.push_str(&detection.message);
+                        }
+                        if detection.escalated {
+                            let _ = app.emit(
+                                "agent-message",
+                                json!({"text": "The agent seems to be stuck, so the run was paused. Resume it or send a message to steer it."}),
+                            );
+                            pause_token.pause();
+                        }
+                    }
+
                     // Small delay between actions
                     tokio::time::sleep(std::time::Duration::from_millis(100)).await;
                 }
@@ -350,6 +1168,9 @@ pub async fn run_agent_loop(
             content: assistant_blocks,
         });
 
+        tool_results.extend(screenshots);
+        tool_results.extend(resume_notes);
+
         // If there were tool results, add user message with results
         if !tool_results.is_empty() {
             messages.push(Message {
@@ -359,14 +1180,61 @@ pub async fn run_agent_loop(
         }
 
         // Trim history
-        trim_history(&mut messages);
+        trim_history(messages, &screenshot_tiers);
 
-        // If no tool use and stop reason is end_turn, the agent is done
-        if !has_tool_use && response.stop_reason == "end_turn" {
-            let _ = app.emit("agent-message", json!({"text": "Task completed."}));
+        if let Some(result) = finished {
+            finish_task(result, &agent_state, &app).await;
+            break;
+        }
+
+        if !has_tool_use && response.stop_reason == "refusal" {
+            let result = TaskResult {
+                status: TaskStatus::Failure,
+                summary: "The model declined to continue with this task.".to_string(),
+                result: None,
+            };
+            finish_task(result, &agent_state, &app).await;
             break;
         }
 
+        note_unusable_turn(messages, &response.stop_reason, has_tool_use);
+
+        // A reply without tool calls isn't a completion; remind the model a few times
+        // before falling back to treating it as the end of the run
+        if !has_tool_use && response.stop_reason == "end_turn" {
+            if reminders_left == 0 {
+                let summary = response
+                    .content
+                    .iter()
+                    .rev()
+                    .find_map(|block| match block {
+                        ContentBlock::Text { text } if !text.trim().is_empty() => {
+                            Some(text.trim().to_string())
+                        }
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| {
+                        "The agent stopped without reporting an outcome.".to_string()
+                    });
+                let result = TaskResult {
+                    status: TaskStatus::Unknown,
+                    summary,
+                    result: None,
+                };
+                finish_task(result, &agent_state, &app).await;
+                break;
+            }
+            reminders_left -= 1;
+            append_user_blocks(
+                messages,
+                vec![ContentBlock::Text {
+                    text: "You stopped without calling task_complete. If the task is finished or cannot be finished, call task_complete with the outcome; otherwise continue working.".to_string(),
+                }],
+            );
+        } else if has_tool_use {
+            reminders_left = s.completion_reminders;
+        }
+
         iteration += 1;
 
         if cancel_token.is_cancelled() {
@@ -378,3 +1246,62 @@ pub async fn run_agent_loop(
     hide_cursor_overlay(&app);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<Message> {
+        vec![
+            Message {
+                role: "user".to_string(),
+                content: vec![ContentBlock::Text {
+                    text: "Open the settings".to_string(),
+                }],
+            },
+            // What a Gemini MALFORMED_FUNCTION_CALL reply leaves behind
+            Message {
+                role: "assistant".to_string(),
+                content: Vec::new(),
+            },
+        ]
+    }
+
+    fn last_text(messages: &[Message]) -> &str {
+        match messages.last().and_then(|m| m.content.last()) {
+            Some(ContentBlock::Text { text }) => text,
+            _ => "",
+        }
+    }
+
+    #[test]
+    fn malformed_and_truncated_calls_get_a_note() {
+        let mut messages = conversation();
+        assert!(note_unusable_turn(&mut messages, "tool_error", false));
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[2].role, "user");
+        assert!(last_text(&messages).contains("malformed"));
+
+        let mut messages = conversation();
+        assert!(note_unusable_turn(&mut messages, "max_tokens", false));
+        assert!(last_text(&messages).contains("cut off"));
+    }
+
+    #[test]
+    fn other_turns_are_left_alone() {
+        for (stop_reason, has_tool_use) in [
+            ("end_turn", false),
+            ("refusal", false),
+            ("tool_use", true),
+            ("max_tokens", true),
+        ] {
+            let mut messages = conversation();
+            assert!(!note_unusable_turn(
+                &mut messages,
+                stop_reason,
+                has_tool_use
+            ));
+            assert_eq!(messages.len(), 2);
+        }
+    }
+}
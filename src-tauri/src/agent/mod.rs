@@ -1,4 +1,14 @@
 pub mod action;
+pub mod approval;
+pub mod compaction;
+pub mod completion;
 pub mod history;
+pub mod integrity;
+pub mod limits;
 pub mod loop_runner;
+pub mod pause;
+pub mod prompt;
+pub mod queue;
+pub mod replay;
+pub mod stuck;
 pub mod tools;
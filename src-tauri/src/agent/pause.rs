@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+/// Shared pause flag for a run, checked by the loop at its safe points.
+#[derive(Debug, Clone)]
+pub struct PauseToken {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl PauseToken {
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(false);
+        Self { tx: Arc::new(tx) }
+    }
+
+    pub fn pause(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.tx.send_replace(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    /// Resolves once the run is no longer paused.
+    pub async fn resumed(&self) {
+        let mut rx = self.tx.subscribe();
+        let _ = rx.wait_for(|paused| !*paused).await;
+    }
+}
+
+impl Default for PauseToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::shell::executor::shell_name;
+use crate::state::Settings;
+
+/// Built-in template, used when `Settings::system_prompt_template` is empty.
+/// `{{name}}` placeholders are filled in by `render`.
+pub const DEFAULT_TEMPLATE: &str = r#"You are an AI agent running on Delegant that controls a computer to accomplish tasks. You can see the screen via screenshots and perform actions using the available tools.
+
+IMPORTANT GUIDELINES:
+- Always take a screenshot first to see the current state of the screen before acting.
+- Screenshots show the full screen. Coordinates are pixel positions from top-left (0,0).
+- Be precise with coordinates when clicking — aim for the center of buttons, links, and text fields.
+- Look carefully at the screenshot to identify clickable UI elements, menus, icons, and text.
+- After performing an action, take a screenshot to verify the result before proceeding.
+- If something doesn't work, try an alternative approach.
+- Use shell commands ({{shell}}) when they are more efficient than GUI interactions.
+- When the task is complete, or you cannot make further progress, call the task_complete tool with the outcome. Do not just stop replying.
+- In your text responses, include a JSON snippet estimating remaining time: {"estimated_seconds": N} where N is your best estimate of seconds remaining to complete the task. Update this estimate as you progress.
+
+ENVIRONMENT:
+- Operating system: {{os}}
+- Today is {{date}}
+- Screen size: {{screen_size}} pixels
+- User: {{username}}
+
+{{custom_instructions}}"#;
+
+/// System prompt settings for one provider, replacing or extending the global ones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PromptOverride {
+    /// Replaces the global template when not empty.
+    #[serde(default)]
+    pub template: String,
+    /// Added after the global custom instructions.
+    #[serde(default)]
+    pub instructions: String,
+}
+
+fn os_name() -> &'static str {
+    match std::env::consts::OS {
+        "windows" => "Windows",
+        "macos" => "macOS",
+        "linux" => "Linux",
+        other => other,
+    }
+}
+
+fn username() -> String {
+    std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Values for the template placeholders.
+pub fn variables(settings: &Settings) -> HashMap<&'static str, String> {
+    HashMap::from([
+        ("os", os_name().to_string()),
+        (
+            "date",
+            chrono::Local::now().format("%A, %B %-d, %Y").to_string(),
+        ),
+        (
+            "screen_size",
+            format!("{}x{}", settings.display_width, settings.display_height),
+        ),
+        ("username", username()),
+        ("shell", shell_name().to_string()),
+    ])
+}
+
+/// Replace `{{name}}` placeholders with their values. Unknown names are left as
+/// they are, so a typo shows up in the preview instead of vanishing.
+pub fn render(template: &str, vars: &HashMap<&str, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let name = after[..end].trim();
+                match vars.get(name) {
+                    Some(value) => out.push_str(value),
+                    None => out.push_str(&rest[start..start + 2 + end + 2]),
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The system prompt for a run with `provider`: the provider's template or the global
+/// one, with the global and provider custom instructions filling
+/// `{{custom_instructions}}`. A template without that placeholder gets the
+/// instructions appended, so they are never silently dropped.
+pub fn build_system_prompt(settings: &Settings, provider: &str) -> String {
+    let profile = settings.provider_prompts.get(provider);
+    let template = match profile.map(|p| p.template.trim()) {
+        Some(template) if !template.is_empty() => template,
+        _ if !settings.system_prompt_template.trim().is_empty() => {
+            settings.system_prompt_template.trim()
+        }
+        _ => DEFAULT_TEMPLATE,
+    };
+
+    let instructions: Vec<&str> = [
+        settings.custom_instructions.trim(),
+        profile.map_or("", |p| p.instructions.trim()),
+    ]
+    .into_iter()
+    .filter(|text| !text.is_empty())
+    .collect();
+    let instructions = if instructions.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "ADDITIONAL INSTRUCTIONS FROM THE USER:\n{}",
+            instructions.join("\n\n")
+        )
+    };
+
+    let mut vars = variables(settings);
+    let prompt = if template.contains("{{custom_instructions}}") {
+        vars.insert("custom_instructions", instructions);
+        render(template, &vars)
+    } else {
+        format!("{}\n\n{}", render(template, &vars), instructions)
+    };
+    prompt.trim().to_string()
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::agent::completion::TaskResult;
+use crate::error::{AppError, AppResult};
+use crate::storage::json_file;
+
+const QUEUE_FILE: &str = "queue.json";
+
+/// Ended tasks kept around for their results; older ones are dropped.
+const MAX_ENDED: usize = 50;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum QueuedTaskStatus {
+    Queued,
+    Running,
+    Finished,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedTask {
+    pub id: String,
+    pub task: String,
+    pub status: QueuedTaskStatus,
+    pub enqueued_at: String,
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    /// Stored run the task executed as.
+    pub run_id: Option<String>,
+    pub result: Option<TaskResult>,
+    pub error: Option<String>,
+}
+
+/// Tasks waiting to run one after another, plus the results of ones that already ran.
+#[derive(Debug, Default)]
+pub struct TaskQueue {
+    tasks: Vec<QueuedTask>,
+}
+
+impl TaskQueue {
+    /// Load the queue saved by a previous session. A task still marked running was
+    /// cut short when the app quit, so it is marked failed rather than rerun.
+    pub fn load(app: &AppHandle) -> AppResult<Self> {
+        let mut tasks: Vec<QueuedTask> = json_file::load(app, QUEUE_FILE)?;
+        for task in tasks
+            .iter_mut()
+            .filter(|t| t.status == QueuedTaskStatus::Running)
+        {
+            task.status = QueuedTaskStatus::Failed;
+            task.ended_at = Some(chrono::Utc::now().to_rfc3339());
+            task.error = Some("Interrupted: the app quit while the task was running".into());
+        }
+        Ok(Self { tasks })
+    }
+
+    /// Save the queue and tell the frontend it changed.
+    pub fn persist(&self, app: &AppHandle) {
+        json_file::persist(app, QUEUE_FILE, "queue-updated", "task queue", &self.tasks);
+    }
+
+    pub fn tasks(&self) -> &[QueuedTask] {
+        &self.tasks
+    }
+
+    pub fn enqueue(&mut self, task: String) -> QueuedTask {
+        let queued = QueuedTask {
+            id: uuid::Uuid::new_v4().to_string(),
+            task,
+            status: QueuedTaskStatus::Queued,
+            enqueued_at: chrono::Utc::now().to_rfc3339(),
+            started_at: None,
+            ended_at: None,
+            run_id: None,
+            result: None,
+            error: None,
+        };
+        self.tasks.push(queued.clone());
+        queued
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut QueuedTask> {
+        self.tasks.iter_mut().find(|t| t.id == id)
+    }
+
+    /// The first waiting task, marked running.
+    pub fn start_next(&mut self) -> Option<QueuedTask> {
+        let next = self
+            .tasks
+            .iter_mut()
+            .find(|t| t.status == QueuedTaskStatus::Queued)?;
+        next.status = QueuedTaskStatus::Running;
+        next.started_at = Some(chrono::Utc::now().to_rfc3339());
+        Some(next.clone())
+    }
+
+    /// Put a task that could not start back in line, keeping its place.
+    pub fn requeue(&mut self, id: &str) {
+        if let Some(task) = self.get_mut(id) {
+            task.status = QueuedTaskStatus::Queued;
+            task.started_at = None;
+        }
+    }
+
+    /// Record how a task ended and drop the oldest ended tasks past `MAX_ENDED`.
+    pub fn finish(
+        &mut self,
+        id: &str,
+        status: QueuedTaskStatus,
+        run_id: Option<String>,
+        result: Option<TaskResult>,
+        error: Option<String>,
+    ) {
+        if let Some(task) = self.get_mut(id) {
+            task.status = status;
+            task.ended_at = Some(chrono::Utc::now().to_rfc3339());
+            task.run_id = run_id;
+            task.result = result;
+            task.error = error;
+        }
+
+        let ended = |t: &QueuedTask| {
+            !matches!(
+                t.status,
+                QueuedTaskStatus::Queued | QueuedTaskStatus::Running
+            )
+        };
+        let mut excess = self
+            .tasks
+            .iter()
+            .filter(|t| ended(t))
+            .count()
+            .saturating_sub(MAX_ENDED);
+        self.tasks.retain(|t| {
+            if excess > 0 && ended(t) {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Put the waiting tasks in the order given by `ids`, which must list each of them
+    /// exactly once. Tasks that already ran or are running keep their place.
+    pub fn reorder(&mut self, ids: &[String]) -> AppResult<()> {
+        let waiting: Vec<&str> = self
+            .tasks
+            .iter()
+            .filter(|t| t.status == QueuedTaskStatus::Queued)
+            .map(|t| t.id.as_str())
+            .collect();
+        let mut sorted_ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+        sorted_ids.sort_unstable();
+        let mut sorted_waiting = waiting.clone();
+        sorted_waiting.sort_unstable();
+        if sorted_ids != sorted_waiting {
+            return Err(AppError::Agent(
+                "Reorder must list every queued task exactly once".into(),
+            ));
+        }
+
+        let mut reordered = ids.iter().map(|id| {
+            let index = self.tasks.iter().position(|t| &t.id == id).unwrap();
+            self.tasks[index].clone()
+        });
+        let mut new_order = Vec::with_capacity(self.tasks.len());
+        for task in &self.tasks {
+            if task.status == QueuedTaskStatus::Queued {
+                new_order.extend(reordered.next());
+            } else {
+                new_order.push(task.clone());
+            }
+        }
+        drop(reordered);
+        self.tasks = new_order;
+        Ok(())
+    }
+
+    /// Cancel a task that has not started yet.
+    pub fn cancel(&mut self, id: &str) -> AppResult<()> {
+        let task = self
+            .get_mut(id)
+            .ok_or_else(|| AppError::Agent(format!("No queued task with id {}", id)))?;
+        match task.status {
+            QueuedTaskStatus::Queued => {
+                task.status = QueuedTaskStatus::Cancelled;
+                task.ended_at = Some(chrono::Utc::now().to_rfc3339());
+                Ok(())
+            }
+            QueuedTaskStatus::Running => Err(AppError::Agent(
+                "Task is already running; stop the agent instead".into(),
+            )),
+            _ => Err(AppError::Agent("Task has already ended".into())),
+        }
+    }
+}
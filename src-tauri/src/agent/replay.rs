@@ -0,0 +1,241 @@
+use base64::Engine;
+use image::imageops::FilterType;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::agent::action::{execute_action, AgentAction};
+use crate::agent::approval::{self, ApprovalPolicy};
+use crate::agent::loop_runner::{ask_approval, wait_while_paused};
+use crate::agent::pause::PauseToken;
+use crate::error::{AppError, AppResult};
+use crate::state::{AgentState, Settings};
+use crate::storage::runs::{self, RecordedAction};
+
+/// Size screenshots are reduced to before comparing, so compression noise and small
+/// details like a blinking caret or a clock don't count as divergence.
+const DIFF_WIDTH: u32 = 64;
+const DIFF_HEIGHT: u32 = 40;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnDivergence {
+    /// Stop the replay.
+    Abort,
+    /// Continue the task with the model from the current screen.
+    HandToModel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplayOptions {
+    /// Pause between actions.
+    pub pace_ms: u64,
+    /// Compare screenshots against the ones the original run saw.
+    pub checkpoints: bool,
+    /// Mean per-pixel difference (0-1) above which a checkpoint counts as diverged.
+    pub diff_threshold: f64,
+    pub on_divergence: OnDivergence,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            pace_ms: 500,
+            checkpoints: true,
+            diff_threshold: 0.1,
+            on_divergence: OnDivergence::Abort,
+        }
+    }
+}
+
+pub enum ReplayOutcome {
+    Completed,
+    Cancelled,
+    Aborted,
+    /// Diverged and should continue as a model-driven run with this task text.
+    HandOff(String),
+}
+
+/// Mean absolute difference between two screenshots after scaling both down to a
+/// small grayscale thumbnail, from 0 (identical) to 1.
+fn perceptual_diff(current_base64: &str, original: &[u8]) -> AppResult<f64> {
+    let current = base64::engine::general_purpose::STANDARD
+        .decode(current_base64)
+        .map_err(|e| AppError::Screenshot(e.to_string()))?;
+    let thumbnail = |bytes: &[u8]| -> AppResult<image::GrayImage> {
+        Ok(image::load_from_memory(bytes)
+            .map_err(|e| AppError::Screenshot(e.to_string()))?
+            .resize_exact(DIFF_WIDTH, DIFF_HEIGHT, FilterType::Triangle)
+            .to_luma8())
+    };
+    let (a, b) = (thumbnail(&current)?, thumbnail(original)?);
+    let total: u64 = a
+        .pixels()
+        .zip(b.pixels())
+        .map(|(p, q)| p.0[0].abs_diff(q.0[0]) as u64)
+        .sum();
+    Ok(total as f64 / (255.0 * (DIFF_WIDTH * DIFF_HEIGHT) as f64))
+}
+
+/// Outcome for a replay that can't follow the recording past step `index`.
+fn diverged(
+    options: &ReplayOptions,
+    task: &str,
+    index: usize,
+    total: usize,
+    reason: &str,
+) -> ReplayOutcome {
+    match options.on_divergence {
+        OnDivergence::Abort => ReplayOutcome::Aborted,
+        OnDivergence::HandToModel => ReplayOutcome::HandOff(format!(
+            "{}\n\n(A replay of an earlier run of this task got through {} of {} recorded actions before {}. Take a screenshot and finish the task from the current state.)",
+            task, index, total, reason
+        )),
+    }
+}
+
+/// Re-execute a stored run's actions without calling a model. Actions go through
+/// the same approval policies and pause points as a model-driven run; a blocked or
+/// rejected action counts as a divergence, as does one that fails.
+#[allow(clippy::too_many_arguments)]
+pub async fn replay_run(
+    run_id: &str,
+    options: &ReplayOptions,
+    settings: &Settings,
+    cancel_token: &CancellationToken,
+    pause_token: &PauseToken,
+    agent_state: &Mutex<AgentState>,
+    app: &AppHandle,
+) -> AppResult<ReplayOutcome> {
+    let runs_dir = runs::runs_dir(app)?;
+    let task = runs::get_run(&runs_dir, run_id)?.meta.task;
+    let actions = runs::recorded_actions(&runs_dir, run_id)?;
+    let total = actions.len();
+    log::info!("Replaying {} actions from run {}", total, run_id);
+
+    for (index, RecordedAction { action, screenshot }) in actions.into_iter().enumerate() {
+        wait_while_paused(pause_token, cancel_token, agent_state, app).await;
+        if cancel_token.is_cancelled() {
+            return Ok(ReplayOutcome::Cancelled);
+        }
+
+        let approved = match approval::policy_for(settings, action.kind()) {
+            ApprovalPolicy::Auto => true,
+            ApprovalPolicy::Deny => false,
+            ApprovalPolicy::Ask => {
+                let decision = ask_approval(
+                    &action,
+                    agent_state,
+                    app,
+                    cancel_token,
+                    settings.approval_timeout_secs,
+                )
+                .await;
+                match decision {
+                    Some(decision) => decision.approved,
+                    None => return Ok(ReplayOutcome::Cancelled),
+                }
+            }
+        };
+        if !approved {
+            log::warn!(
+                "Replay step {}/{} ({}) was not approved",
+                index + 1,
+                total,
+                action.kind()
+            );
+            let _ = app.emit(
+                "agent-message",
+                json!({ "text": format!("Replay step {} ({}) was blocked or rejected.", index + 1, action.description()) }),
+            );
+            return Ok(diverged(
+                options,
+                &task,
+                index,
+                total,
+                "the next one was blocked or rejected by the user",
+            ));
+        }
+
+        let _ = app.emit(
+            "action-executed",
+            json!({
+                "action_type": action.kind(),
+                "description": format!("Replay {}/{}: {}", index + 1, total, action.description()),
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+                "iteration": index
+            }),
+        );
+
+        let result = tokio::select! {
+            res = execute_action(
+                &action,
+                settings.display_width,
+                settings.display_height,
+                settings.shell_timeout_secs,
+            ) => res,
+            _ = cancel_token.cancelled() => return Ok(ReplayOutcome::Cancelled),
+        };
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                log::warn!(
+                    "Replay step {}/{} ({}) failed: {}",
+                    index + 1,
+                    total,
+                    action.kind(),
+                    e
+                );
+                let _ = app.emit(
+                    "agent-message",
+                    json!({ "text": format!("Replay step {} ({}) failed: {}", index + 1, action.description(), e) }),
+                );
+                return Ok(diverged(
+                    options,
+                    &task,
+                    index,
+                    total,
+                    &format!("the next one failed ({})", e),
+                ));
+            }
+        };
+
+        if let (AgentAction::Screenshot, Some(base64)) = (&action, &result.base64) {
+            let _ = app.emit(
+                "screenshot-updated",
+                json!({"base64": base64, "w": settings.display_width, "h": settings.display_height}),
+            );
+
+            if let (true, Some(original)) = (options.checkpoints, screenshot) {
+                let diff = perceptual_diff(base64, &original)?;
+                let passed = diff <= options.diff_threshold;
+                let _ = app.emit(
+                    "replay-checkpoint",
+                    json!({ "step": index + 1, "total": total, "diff": diff, "passed": passed }),
+                );
+                if !passed {
+                    log::warn!(
+                        "Replay diverged at step {}/{} (diff {:.3})",
+                        index + 1,
+                        total,
+                        diff
+                    );
+                    return Ok(diverged(
+                        options,
+                        &task,
+                        index,
+                        total,
+                        "the screen stopped matching the recording",
+                    ));
+                }
+            }
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(options.pace_ms)).await;
+    }
+
+    Ok(ReplayOutcome::Completed)
+}
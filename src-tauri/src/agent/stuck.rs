@@ -0,0 +1,236 @@
+use base64::Engine;
+use image::GrayImage;
+use serde::Serialize;
+
+use crate::agent::action::AgentAction;
+use crate::state::Settings;
+
+/// Brightness change below which a pixel is taken as JPEG noise rather than a change.
+const PIXEL_TOLERANCE: u8 = 24;
+
+/// Fewest changed pixels for the screen to count as changed. Low enough that typing
+/// a single character or ticking a checkbox counts as progress.
+const MIN_CHANGED_PIXELS: usize = 12;
+
+/// A detected stuck loop, reported in `stuck-detected`.
+#[derive(Debug, Clone, Serialize)]
+pub struct StuckDetection {
+    /// `repeated_action` or `unchanged_screen`.
+    pub kind: String,
+    /// How many actions the pattern has lasted.
+    pub count: u32,
+    /// Corrective note for the model.
+    pub message: String,
+    /// True once corrective notes have stopped helping and the run is being paused.
+    pub escalated: bool,
+}
+
+/// Watches for the model repeating itself: the same action over and over, or a
+/// screen that stays the same while actions keep being sent. Thresholds of 0
+/// disable the matching check.
+pub struct StuckDetector {
+    repeat_threshold: u32,
+    unchanged_threshold: u32,
+    max_nudges: u32,
+    last_action: Option<String>,
+    repeat_count: u32,
+    /// The latest screenshot in grayscale, at full resolution.
+    last_screen: Option<GrayImage>,
+    actions_since_change: u32,
+    nudges: u32,
+}
+
+impl StuckDetector {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            repeat_threshold: settings.stuck_repeat_threshold,
+            unchanged_threshold: settings.stuck_unchanged_threshold,
+            max_nudges: settings.stuck_max_nudges,
+            last_action: None,
+            repeat_count: 0,
+            last_screen: None,
+            actions_since_change: 0,
+            nudges: 0,
+        }
+    }
+
+    /// Record an action about to run. Observation-only actions are ignored, since
+    /// the model legitimately interleaves them with everything else.
+    pub fn record_action(&mut self, action: &AgentAction) -> Option<StuckDetection> {
+        if matches!(
+            action,
+            AgentAction::Screenshot | AgentAction::Wait { .. } | AgentAction::TextEditorView { .. }
+        ) {
+            return None;
+        }
+
+        let key = serde_json::to_string(action).unwrap_or_default();
+        if self.last_action.as_deref() == Some(key.as_str()) {
+            self.repeat_count += 1;
+        } else {
+            self.last_action = Some(key);
+            self.repeat_count = 1;
+        }
+        self.actions_since_change += 1;
+
+        if self.repeat_threshold == 0 || self.repeat_count < self.repeat_threshold {
+            return None;
+        }
+        let count = self.repeat_count;
+        self.repeat_count = 0;
+        Some(self.detection(
+            "repeated_action",
+            count,
+            format!(
+                "You have sent the same action ({}) {} times in a row. It is not having the intended effect; stop repeating it and try a different approach.",
+                action.description(),
+                count
+            ),
+        ))
+    }
+
+    /// Record a screenshot taken after the latest actions.
+    pub fn record_screenshot(&mut self, base64: &str) -> Option<StuckDetection> {
+        let current = decode_gray(base64);
+        // A screenshot that can't be read counts as a change, so it never causes a nudge
+        let changed = match (&self.last_screen, &current) {
+            (Some(last), Some(current)) => screen_changed(last, current),
+            _ => true,
+        };
+        self.last_screen = current;
+
+        if changed {
+            self.actions_since_change = 0;
+            // The screen moved on, so earlier nudges worked
+            self.nudges = 0;
+            return None;
+        }
+
+        if self.unchanged_threshold == 0 || self.actions_since_change < self.unchanged_threshold {
+            return None;
+        }
+        let count = self.actions_since_change;
+        self.actions_since_change = 0;
+        Some(self.detection(
+            "unchanged_screen",
+            count,
+            format!(
+                "The screen has not changed over your last {} actions. Re-examine the screenshot, check that you are targeting the right element or window, and try a different approach.",
+                count
+            ),
+        ))
+    }
+
+    fn detection(&mut self, kind: &str, count: u32, message: String) -> StuckDetection {
+        self.nudges += 1;
+        let escalated = self.nudges > self.max_nudges;
+        if escalated {
+            self.nudges = 0;
+        }
+        StuckDetection {
+            kind: kind.to_string(),
+            count,
+            message,
+            escalated,
+        }
+    }
+}
+
+fn decode_gray(base64: &str) -> Option<GrayImage> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(base64)
+        .ok()?;
+    Some(image::load_from_memory(&bytes).ok()?.to_luma8())
+}
+
+/// Whether enough pixels differ between two screenshots to call it a different screen.
+fn screen_changed(last: &GrayImage, current: &GrayImage) -> bool {
+    if last.dimensions() != current.dimensions() {
+        return true;
+    }
+    last.pixels()
+        .zip(current.pixels())
+        .filter(|(a, b)| a.0[0].abs_diff(b.0[0]) > PIXEL_TOLERANCE)
+        .count()
+        >= MIN_CHANGED_PIXELS
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::codecs::jpeg::JpegEncoder;
+    use image::{Rgb, RgbImage};
+
+    use super::*;
+
+    /// A window with an empty text field, and `typed` characters in the field.
+    fn screen(typed: u32) -> String {
+        let mut img = RgbImage::from_pixel(1280, 800, Rgb([240, 240, 240]));
+        for x in 400..800 {
+            for y in 300..330 {
+                let border = x == 400 || x == 799 || y == 300 || y == 329;
+                img.put_pixel(
+                    x,
+                    y,
+                    if border {
+                        Rgb([120, 120, 120])
+                    } else {
+                        Rgb([255; 3])
+                    },
+                );
+            }
+        }
+        // Each character is a 6x10 glyph outline, about what a small UI font draws
+        for i in 0..typed {
+            let left = 406 + i * 8;
+            for x in left..left + 6 {
+                for y in 310..320 {
+                    if x == left || x == left + 5 || y == 310 || y == 319 {
+                        img.put_pixel(x, y, Rgb([20, 20, 20]));
+                    }
+                }
+            }
+        }
+        // Encoded the way screen capture does it
+        let mut buf = Cursor::new(Vec::new());
+        img.write_with_encoder(JpegEncoder::new_with_quality(&mut buf, 72))
+            .unwrap();
+        base64::engine::general_purpose::STANDARD.encode(buf.into_inner())
+    }
+
+    fn detector() -> StuckDetector {
+        StuckDetector::from_settings(&Settings {
+            stuck_unchanged_threshold: 2,
+            ..Settings::default()
+        })
+    }
+
+    fn act(detector: &mut StuckDetector, key: &str) {
+        detector.record_action(&AgentAction::Key {
+            combo: key.to_string(),
+        });
+    }
+
+    #[test]
+    fn typing_a_character_counts_as_a_change() {
+        let mut detector = detector();
+        assert!(detector.record_screenshot(&screen(0)).is_none());
+        for typed in 1..=4 {
+            act(&mut detector, &format!("k{}", typed));
+            assert!(detector.record_screenshot(&screen(typed)).is_none());
+        }
+    }
+
+    #[test]
+    fn identical_screens_are_reported() {
+        let mut detector = detector();
+        detector.record_screenshot(&screen(3));
+        act(&mut detector, "a");
+        assert!(detector.record_screenshot(&screen(3)).is_none());
+        act(&mut detector, "b");
+        let detection = detector.record_screenshot(&screen(3)).unwrap();
+        assert_eq!(detection.kind, "unchanged_screen");
+        assert_eq!(detection.count, 2);
+    }
+}
@@ -1,19 +1,132 @@
 use serde_json::json;
 
+use crate::ai::provider::ProviderCapabilities;
 use crate::ai::types::ToolDefinition;
 
 pub fn build_tool_definitions(
-    provider: &str,
+    capabilities: &ProviderCapabilities,
     display_width: u32,
     display_height: u32,
+    memory: bool,
 ) -> Vec<ToolDefinition> {
-    match provider {
-        "anthropic" => build_anthropic_tools(display_width, display_height),
-        "openai" => build_openai_tools(display_width, display_height),
-        "openrouter" => build_openai_tools(display_width, display_height),
-        "ollama" => build_openai_tools(display_width, display_height),
-        _ => build_anthropic_tools(display_width, display_height),
+    let mut tools = if capabilities.native_computer_use {
+        build_anthropic_tools(display_width, display_height)
+    } else {
+        build_openai_tools(display_width, display_height)
+    };
+    tools.push(task_complete_tool());
+    if memory {
+        tools.extend(memory_tools());
     }
+    tools
+}
+
+/// The only way a run ends successfully: the model reports its outcome explicitly
+/// instead of the loop guessing from a turn without tool calls.
+fn task_complete_tool() -> ToolDefinition {
+    ToolDefinition {
+        name: "task_complete".to_string(),
+        tool_type: None,
+        description: Some("Call this exactly once when you are finished, to end the task and report the outcome. Use status \"success\" only if you verified the task is done, \"failure\" if you tried and could not do it, and \"blocked\" if you need something from the user (a login, a decision, missing information).".to_string()),
+        input_schema: Some(json!({
+            "type": "object",
+            "properties": {
+                "status": {
+                    "type": "string",
+                    "enum": ["success", "failure", "blocked"]
+                },
+                "summary": {
+                    "type": "string",
+                    "description": "What you did and what the outcome was, for the user"
+                },
+                "result": {
+                    "type": "object",
+                    "description": "Optional structured output the task asked for (values found, file paths created, ...)"
+                }
+            },
+            "required": ["status", "summary"]
+        })),
+        display_width_px: None,
+        display_height_px: None,
+        display_number: None,
+    }
+}
+
+/// Long-term memory shared by all tasks, for facts worth knowing next time.
+fn memory_tools() -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "memory_search".to_string(),
+            tool_type: None,
+            description: Some("Search your long-term memory from earlier tasks by keywords. Returns matching memory ids, titles and a preview.".to_string()),
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Keywords to look for, e.g. 'vpn client'"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of results (default 5)"
+                    }
+                },
+                "required": ["query"]
+            })),
+            display_width_px: None,
+            display_height_px: None,
+            display_number: None,
+        },
+        ToolDefinition {
+            name: "memory_read".to_string(),
+            tool_type: None,
+            description: Some("Read the full content of a memory by its id.".to_string()),
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Memory id from memory_search or the task message"
+                    }
+                },
+                "required": ["id"]
+            })),
+            display_width_px: None,
+            display_height_px: None,
+            display_number: None,
+        },
+        ToolDefinition {
+            name: "memory_write".to_string(),
+            tool_type: None,
+            description: Some("Save a fact that will still be useful in future tasks (where an application is installed, which account or browser profile to use, a working procedure). Pass the id of an existing memory to correct it instead of adding a duplicate. Do not store passwords or other secrets.".to_string()),
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "Short title naming what the memory is about"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The fact itself, self-contained"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Keywords to find it by"
+                    },
+                    "id": {
+                        "type": "string",
+                        "description": "Id of the memory to update; omit to create a new one"
+                    }
+                },
+                "required": ["title", "content"]
+            })),
+            display_width_px: None,
+            display_height_px: None,
+            display_number: None,
+        },
+    ]
 }
 
 fn build_anthropic_tools(display_width: u32, display_height: u32) -> Vec<ToolDefinition> {
@@ -1,33 +1,96 @@
-use reqwest::Client;
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
 use serde_json::json;
 
+use crate::agent::history::stable_prefix_end;
 use crate::error::{AppError, AppResult};
 
-use super::types::{AiResponse, ContentBlock, Message, ToolDefinition};
+use super::provider::{AiProvider, ProviderCapabilities, StreamEvent, StreamSink};
+use super::retry::error_from_response;
+use super::streaming::SseReader;
+use super::types::{AiResponse, ContentBlock, Message, ModelOption, ToolDefinition, Usage};
+
+/// Minimum `budget_tokens` the API accepts when extended thinking is enabled.
+const MIN_THINKING_BUDGET: u32 = 1024;
+
+/// Attach an ephemeral cache breakpoint to the last content block of a message.
+/// Thinking blocks can't carry `cache_control`, so they're skipped.
+fn mark_cache_breakpoint(message: &mut serde_json::Value) {
+    if let Some(last) = message["content"].as_array_mut().and_then(|blocks| {
+        blocks.iter_mut().rev().find(|b| {
+            !matches!(
+                b["type"].as_str(),
+                Some("thinking") | Some("redacted_thinking")
+            )
+        })
+    }) {
+        last["cache_control"] = json!({ "type": "ephemeral" });
+    }
+}
+
+/// A content block still being assembled from stream deltas.
+enum PartialBlock {
+    Text(String),
+    ToolUse {
+        id: String,
+        name: String,
+        input_json: String,
+    },
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    RedactedThinking(String),
+    Other,
+}
 
 pub struct AnthropicClient {
     client: Client,
     api_key: String,
     model: String,
+    /// Extended thinking budget in tokens; 0 disables thinking.
+    thinking_budget: u32,
 }
 
 impl AnthropicClient {
-    pub fn new(api_key: &str, model: &str) -> Self {
+    pub fn new(api_key: &str, model: &str, thinking_budget: u32) -> Self {
         Self {
             client: Client::new(),
             api_key: api_key.to_string(),
             model: model.to_string(),
+            thinking_budget: match thinking_budget {
+                0 => 0,
+                budget => budget.max(MIN_THINKING_BUDGET),
+            },
         }
     }
+}
+
+#[async_trait]
+impl AiProvider for AnthropicClient {
+    fn id(&self) -> &str {
+        "anthropic"
+    }
 
-    pub async fn send(
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            native_computer_use: true,
+            vision: true,
+            tool_calling: true,
+        }
+    }
+
+    async fn send(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[ToolDefinition],
+        on_event: StreamSink<'_>,
     ) -> AppResult<AiResponse> {
         // Build the Anthropic-format messages
-        let api_messages: Vec<serde_json::Value> = messages
+        let mut api_messages: Vec<serde_json::Value> = messages
             .iter()
             .map(|msg| {
                 let content: Vec<serde_json::Value> = msg
@@ -67,6 +130,18 @@ impl AnthropicClient {
                             }
                             v
                         }
+                        ContentBlock::Thinking {
+                            thinking,
+                            signature,
+                        } => json!({
+                            "type": "thinking",
+                            "thinking": thinking,
+                            "signature": signature
+                        }),
+                        ContentBlock::RedactedThinking { data } => json!({
+                            "type": "redacted_thinking",
+                            "data": data
+                        }),
                     })
                     .collect();
                 json!({
@@ -76,8 +151,18 @@ impl AnthropicClient {
             })
             .collect();
 
+        // Prompt caching allows at most 4 breakpoints: tools and system take two, the
+        // other two go on the history. One sits at the end of the prefix screenshot
+        // trimming will never touch, so it keeps hitting after a trim; the other rolls
+        // with the latest turn so the next iteration reads everything before it.
+        let last = api_messages.len().checked_sub(1);
+        let stable = stable_prefix_end(messages).filter(|&i| Some(i) != last);
+        for index in [stable, last].into_iter().flatten() {
+            mark_cache_breakpoint(&mut api_messages[index]);
+        }
+
         // Build tools in Anthropic format
-        let api_tools: Vec<serde_json::Value> = tools
+        let mut api_tools: Vec<serde_json::Value> = tools
             .iter()
             .map(|tool| {
                 let mut t = json!({ "name": tool.name });
@@ -102,70 +187,221 @@ impl AnthropicClient {
                 t
             })
             .collect();
+        if let Some(tool) = api_tools.last_mut() {
+            tool["cache_control"] = json!({ "type": "ephemeral" });
+        }
 
-        let body = json!({
+        let mut body = json!({
             "model": self.model,
-            "max_tokens": 4096,
-            "system": system,
+            // The thinking budget counts towards max_tokens, so leave room for the reply
+            "max_tokens": 4096 + self.thinking_budget,
+            "system": [{
+                "type": "text",
+                "text": system,
+                "cache_control": { "type": "ephemeral" }
+            }],
             "tools": api_tools,
-            "messages": api_messages
+            "messages": api_messages,
+            "stream": true
         });
+        let mut betas = vec!["computer-use-2025-01-24"];
+        if self.thinking_budget > 0 {
+            body["thinking"] = json!({
+                "type": "enabled",
+                "budget_tokens": self.thinking_budget
+            });
+            // Lets the model think between tool calls, not just before the first one
+            betas.push("interleaved-thinking-2025-05-14");
+        }
 
         let resp = self
             .client
             .post("https://api.anthropic.com/v1/messages")
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
-            .header("anthropic-beta", "computer-use-2025-01-24")
+            .header("anthropic-beta", betas.join(","))
             .header("content-type", "application/json")
             .json(&body)
             .send()
             .await?;
 
-        let status = resp.status();
-        let text = resp.text().await?;
-
-        if !status.is_success() {
-            return Err(AppError::AiProvider(format!(
-                "Anthropic API error {}: {}",
-                status, text
-            )));
+        if !resp.status().is_success() {
+            return Err(error_from_response("Anthropic", resp).await);
         }
 
-        let parsed: serde_json::Value =
-            serde_json::from_str(&text).map_err(|e| AppError::AiProvider(e.to_string()))?;
+        let mut reader = SseReader::new(resp);
+        let mut blocks: BTreeMap<u64, PartialBlock> = BTreeMap::new();
+        let mut stop_reason = "end_turn".to_string();
+        let mut usage = Usage::default();
 
-        let stop_reason = parsed["stop_reason"]
-            .as_str()
-            .unwrap_or("end_turn")
-            .to_string();
+        while let Some(event) = reader.next_event().await? {
+            let data: serde_json::Value = serde_json::from_str(&event.data)
+                .map_err(|e| AppError::AiProvider(e.to_string()))?;
 
-        let content_blocks = parsed["content"]
-            .as_array()
-            .ok_or_else(|| AppError::AiProvider("No content in response".into()))?;
-
-        let mut content = Vec::new();
-        for block in content_blocks {
-            match block["type"].as_str() {
-                Some("text") => {
-                    content.push(ContentBlock::Text {
-                        text: block["text"].as_str().unwrap_or("").to_string(),
-                    });
+            match event.event.as_deref().or_else(|| data["type"].as_str()) {
+                Some("message_start") => {
+                    let u = &data["message"]["usage"];
+                    usage.input_tokens = u["input_tokens"].as_u64().unwrap_or(0);
+                    usage.output_tokens = u["output_tokens"].as_u64().unwrap_or(0);
+                    usage.cache_creation_input_tokens =
+                        u["cache_creation_input_tokens"].as_u64().unwrap_or(0);
+                    usage.cache_read_input_tokens =
+                        u["cache_read_input_tokens"].as_u64().unwrap_or(0);
+                }
+                Some("content_block_start") => {
+                    let index = data["index"].as_u64().unwrap_or(0);
+                    let block = &data["content_block"];
+                    let partial = match block["type"].as_str() {
+                        Some("text") => {
+                            PartialBlock::Text(block["text"].as_str().unwrap_or("").to_string())
+                        }
+                        Some("tool_use") => PartialBlock::ToolUse {
+                            id: block["id"].as_str().unwrap_or("").to_string(),
+                            name: block["name"].as_str().unwrap_or("").to_string(),
+                            input_json: String::new(),
+                        },
+                        Some("thinking") => PartialBlock::Thinking {
+                            thinking: block["thinking"].as_str().unwrap_or("").to_string(),
+                            signature: block["signature"].as_str().unwrap_or("").to_string(),
+                        },
+                        Some("redacted_thinking") => PartialBlock::RedactedThinking(
+                            block["data"].as_str().unwrap_or("").to_string(),
+                        ),
+                        _ => PartialBlock::Other,
+                    };
+                    blocks.insert(index, partial);
+                }
+                Some("content_block_delta") => {
+                    let index = data["index"].as_u64().unwrap_or(0);
+                    let delta = &data["delta"];
+                    match (blocks.get_mut(&index), delta["type"].as_str()) {
+                        (Some(PartialBlock::Text(text)), Some("text_delta")) => {
+                            let piece = delta["text"].as_str().unwrap_or("");
+                            text.push_str(piece);
+                            on_event(StreamEvent::TextDelta(piece.to_string()));
+                        }
+                        (
+                            Some(PartialBlock::ToolUse { input_json, .. }),
+                            Some("input_json_delta"),
+                        ) => {
+                            input_json.push_str(delta["partial_json"].as_str().unwrap_or(""));
+                        }
+                        (Some(PartialBlock::Thinking { thinking, .. }), Some("thinking_delta")) => {
+                            let piece = delta["thinking"].as_str().unwrap_or("");
+                            thinking.push_str(piece);
+                            on_event(StreamEvent::ThinkingDelta(piece.to_string()));
+                        }
+                        (
+                            Some(PartialBlock::Thinking { signature, .. }),
+                            Some("signature_delta"),
+                        ) => {
+                            signature.push_str(delta["signature"].as_str().unwrap_or(""));
+                        }
+                        _ => {}
+                    }
                 }
-                Some("tool_use") => {
-                    content.push(ContentBlock::ToolUse {
-                        id: block["id"].as_str().unwrap_or("").to_string(),
-                        name: block["name"].as_str().unwrap_or("").to_string(),
-                        input: block["input"].clone(),
+                Some("message_delta") => {
+                    if let Some(reason) = data["delta"]["stop_reason"].as_str() {
+                        stop_reason = reason.to_string();
+                    }
+                    // Output token count here is cumulative for the whole message
+                    if let Some(output) = data["usage"]["output_tokens"].as_u64() {
+                        usage.output_tokens = output;
+                    }
+                }
+                Some("error") => {
+                    // Mid-stream failures arrive as events, so map them onto HTTP semantics
+                    let status = match data["error"]["type"].as_str() {
+                        Some("overloaded_error") => 529,
+                        Some("rate_limit_error") => 429,
+                        Some("api_error") => 500,
+                        _ => 400,
+                    };
+                    return Err(AppError::ProviderHttp {
+                        provider: "Anthropic".to_string(),
+                        status: StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_REQUEST),
+                        body: data["error"].to_string(),
+                        retry_after: None,
                     });
                 }
+                Some("message_stop") => break,
                 _ => {}
             }
         }
 
+        let content = blocks
+            .into_values()
+            .filter_map(|block| match block {
+                PartialBlock::Text(text) => Some(ContentBlock::Text { text }),
+                PartialBlock::ToolUse {
+                    id,
+                    name,
+                    input_json,
+                } => {
+                    let input = if input_json.trim().is_empty() {
+                        json!({})
+                    } else {
+                        serde_json::from_str(&input_json).unwrap_or(json!({}))
+                    };
+                    Some(ContentBlock::ToolUse { id, name, input })
+                }
+                PartialBlock::Thinking {
+                    thinking,
+                    signature,
+                } => Some(ContentBlock::Thinking {
+                    thinking,
+                    signature,
+                }),
+                PartialBlock::RedactedThinking(data) => {
+                    Some(ContentBlock::RedactedThinking { data })
+                }
+                PartialBlock::Other => None,
+            })
+            .collect();
+
         Ok(AiResponse {
             content,
             stop_reason,
+            usage,
         })
     }
+
+    async fn list_models(&self) -> AppResult<Vec<ModelOption>> {
+        let resp = self
+            .client
+            .get("https://api.anthropic.com/v1/models")
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(AppError::AiProvider(format!(
+                "Anthropic model fetch failed: HTTP {}",
+                status
+            )));
+        }
+
+        let parsed: serde_json::Value = resp.json().await?;
+        let mut models = parsed["data"].as_array().cloned().unwrap_or_default();
+        models.sort_by(|a, b| {
+            b["created_at"]
+                .as_str()
+                .unwrap_or("")
+                .cmp(a["created_at"].as_str().unwrap_or(""))
+        });
+
+        Ok(models
+            .iter()
+            .filter_map(|m| {
+                let id = m["id"].as_str()?;
+                let label = m["display_name"].as_str().unwrap_or(id);
+                Some(ModelOption {
+                    id: id.to_string(),
+                    label: label.to_string(),
+                })
+            })
+            .collect())
+    }
 }
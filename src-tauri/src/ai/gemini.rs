@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+use super::provider::{AiProvider, ProviderCapabilities, StreamEvent, StreamSink};
+use super::retry::error_from_response;
+use super::streaming::SseReader;
+use super::types::{AiResponse, ContentBlock, Message, ModelOption, ToolDefinition, Usage};
+
+const MAX_OUTPUT_TOKENS: u32 = 4096;
+
+pub const DEFAULT_GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+pub struct GeminiClient {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+    /// Thinking budget in tokens; 0 leaves thinking at the model's default.
+    thinking_budget: u32,
+    /// Thought signatures returned alongside function calls, keyed by tool use id.
+    /// Gemini rejects follow-up turns that drop them, and `ContentBlock` has nowhere
+    /// to carry them, so the client holds on to them for the length of the run.
+    signatures: Mutex<HashMap<String, String>>,
+}
+
+impl GeminiClient {
+    pub fn new(api_key: &str, base_url: &str, model: &str, thinking_budget: u32) -> Self {
+        let base_url = match base_url.trim().trim_end_matches('/') {
+            "" => DEFAULT_GEMINI_BASE_URL,
+            url => url,
+        };
+        Self {
+            client: Client::new(),
+            api_key: api_key.to_string(),
+            base_url: base_url.to_string(),
+            // Model ids from the models endpoint carry a `models/` prefix
+            model: model.trim_start_matches("models/").to_string(),
+            thinking_budget,
+            signatures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Convert the conversation to Gemini `contents`, with tool calls as function parts.
+    fn to_contents(&self, messages: &[Message]) -> Vec<Value> {
+        let signatures = self.signatures.lock().unwrap();
+        // functionResponse parts are matched by name, so remember which tool each id called
+        let mut tool_names: HashMap<&str, &str> = HashMap::new();
+        let mut contents = Vec::new();
+
+        for msg in messages {
+            let role = if msg.role == "assistant" {
+                "model"
+            } else {
+                "user"
+            };
+            let parts: Vec<Value> = msg
+                .content
+                .iter()
+                .filter_map(|block| {
+                    Some(match block {
+                        ContentBlock::Text { text } => json!({ "text": text }),
+                        ContentBlock::Image { source } => json!({
+                            "inlineData": {
+                                "mimeType": source.media_type,
+                                "data": source.data
+                            }
+                        }),
+                        ContentBlock::ToolUse { id, name, input } => {
+                            tool_names.insert(id, name);
+                            let mut part = json!({
+                                "functionCall": { "name": name, "args": input }
+                            });
+                            if let Some(signature) = signatures.get(id) {
+                                part["thoughtSignature"] = json!(signature);
+                            }
+                            part
+                        }
+                        ContentBlock::ToolResult {
+                            tool_use_id,
+                            content,
+                            is_error,
+                        } => {
+                            let name = tool_names.get(tool_use_id.as_str()).copied().unwrap_or("");
+                            let response = if *is_error == Some(true) {
+                                json!({ "error": content })
+                            } else {
+                                json!({ "output": content })
+                            };
+                            json!({
+                                "functionResponse": { "name": name, "response": response }
+                            })
+                        }
+                        // Gemini carries its reasoning state in thought signatures instead
+                        ContentBlock::Thinking { .. } | ContentBlock::RedactedThinking { .. } => {
+                            return None
+                        }
+                    })
+                })
+                .collect();
+
+            if !parts.is_empty() {
+                contents.push(json!({ "role": role, "parts": parts }));
+            }
+        }
+
+        contents
+    }
+}
+
+#[async_trait]
+impl AiProvider for GeminiClient {
+    fn id(&self) -> &str {
+        "gemini"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            native_computer_use: false,
+            vision: true,
+            tool_calling: true,
+        }
+    }
+
+    async fn send(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        on_event: StreamSink<'_>,
+    ) -> AppResult<AiResponse> {
+        let declarations = to_function_declarations(tools);
+
+        let mut body = json!({
+            "systemInstruction": { "parts": [{ "text": system }] },
+            "contents": self.to_contents(messages),
+            "generationConfig": { "maxOutputTokens": MAX_OUTPUT_TOKENS }
+        });
+        if !declarations.is_empty() {
+            body["tools"] = json!([{ "functionDeclarations": declarations }]);
+        }
+        if self.thinking_budget > 0 {
+            body["generationConfig"]["thinkingConfig"] = json!({
+                "thinkingBudget": self.thinking_budget,
+                "includeThoughts": true
+            });
+            body["generationConfig"]["maxOutputTokens"] =
+                json!(MAX_OUTPUT_TOKENS + self.thinking_budget);
+        }
+
+        let resp = self
+            .client
+            .post(format!(
+                "{}/models/{}:streamGenerateContent?alt=sse",
+                self.base_url, self.model
+            ))
+            .header("x-goog-api-key", &self.api_key)
+            .header("content-type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(error_from_response("Gemini", resp).await);
+        }
+
+        let mut reader = SseReader::new(resp);
+        let mut text = String::new();
+        let mut calls: Vec<ContentBlock> = Vec::new();
+        let mut finish_reason: Option<String> = None;
+        let mut usage = Usage::default();
+
+        while let Some(event) = reader.next_event().await? {
+            let chunk: Value = serde_json::from_str(&event.data)
+                .map_err(|e| AppError::AiProvider(format!("Gemini stream parse error: {}", e)))?;
+
+            if !chunk["error"].is_null() {
+                return Err(AppError::AiProvider(format!(
+                    "Gemini API error: {}",
+                    chunk["error"]
+                )));
+            }
+
+            // Usage is cumulative, so the last chunk carrying it wins
+            let meta = &chunk["usageMetadata"];
+            if meta.is_object() {
+                let count = |field: &str| meta[field].as_u64().unwrap_or(0);
+                let cached = count("cachedContentTokenCount");
+                usage = Usage {
+                    input_tokens: count("promptTokenCount").saturating_sub(cached),
+                    output_tokens: count("candidatesTokenCount") + count("thoughtsTokenCount"),
+                    cache_creation_input_tokens: 0,
+                    cache_read_input_tokens: cached,
+                };
+            }
+
+            let candidate = &chunk["candidates"][0];
+            if let Some(reason) = candidate["finishReason"].as_str() {
+                finish_reason = Some(reason.to_string());
+            }
+
+            for part in candidate["content"]["parts"]
+                .as_array()
+                .into_iter()
+                .flatten()
+            {
+                if let Some(call) = part.get("functionCall") {
+                    let id = call["id"]
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("call_{}", Uuid::new_v4()));
+                    if let Some(signature) = part["thoughtSignature"].as_str() {
+                        self.signatures
+                            .lock()
+                            .unwrap()
+                            .insert(id.clone(), signature.to_string());
+                    }
+                    calls.push(ContentBlock::ToolUse {
+                        id,
+                        name: call["name"].as_str().unwrap_or("").to_string(),
+                        input: call.get("args").cloned().unwrap_or(json!({})),
+                    });
+                } else if part["thought"].as_bool() == Some(true) {
+                    // Thought summaries are shown as thinking, not as part of the reply
+                    if let Some(piece) = part["text"].as_str() {
+                        on_event(StreamEvent::ThinkingDelta(piece.to_string()));
+                    }
+                } else if let Some(piece) = part["text"].as_str() {
+                    if !piece.is_empty() {
+                        text.push_str(piece);
+                        on_event(StreamEvent::TextDelta(piece.to_string()));
+                    }
+                }
+            }
+        }
+
+        let stop_reason = stop_reason_for(finish_reason.as_deref(), !calls.is_empty());
+
+        let mut content = Vec::new();
+        if !text.is_empty() {
+            content.push(ContentBlock::Text { text });
+        }
+        content.extend(calls);
+
+        Ok(AiResponse {
+            content,
+            stop_reason,
+            usage,
+        })
+    }
+
+    async fn list_models(&self) -> AppResult<Vec<ModelOption>> {
+        let resp = self
+            .client
+            .get(format!("{}/models?pageSize=1000", self.base_url))
+            .header("x-goog-api-key", &self.api_key)
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(AppError::AiProvider(format!(
+                "Gemini model fetch failed: HTTP {}",
+                status
+            )));
+        }
+
+        let parsed: Value = resp.json().await?;
+        let models = parsed["models"].as_array().cloned().unwrap_or_default();
+
+        Ok(models
+            .iter()
+            .filter(|m| {
+                m["supportedGenerationMethods"]
+                    .as_array()
+                    .is_some_and(|methods| methods.iter().any(|x| x == "generateContent"))
+            })
+            .filter_map(|m| {
+                let id = m["name"].as_str()?.trim_start_matches("models/");
+                let label = m["displayName"].as_str().unwrap_or(id);
+                Some(ModelOption {
+                    id: id.to_string(),
+                    label: label.to_string(),
+                })
+            })
+            .collect())
+    }
+}
+
+/// Convert tool definitions to Gemini function declarations.
+fn to_function_declarations(tools: &[ToolDefinition]) -> Vec<Value> {
+    tools
+        .iter()
+        .filter(|t| t.input_schema.is_some())
+        .map(|tool| {
+            json!({
+                "name": tool.name,
+                "description": tool.description.clone().unwrap_or_default(),
+                "parameters": tool.input_schema.clone().map(gemini_schema).unwrap_or(json!({}))
+            })
+        })
+        .collect()
+}
+
+/// Gemini rejects object schemas without properties, so free-form objects are asked
+/// for as a JSON string instead. `TaskResult::from_tool_input` decodes them again.
+fn gemini_schema(mut schema: Value) -> Value {
+    let Some(map) = schema.as_object_mut() else {
+        return schema;
+    };
+    let has_properties = match map.get("properties") {
+        Some(Value::Object(props)) => !props.is_empty(),
+        _ => false,
+    };
+    let free_form = map.get("type").and_then(Value::as_str) == Some("object") && !has_properties;
+    if free_form {
+        let description = map.get("description").and_then(Value::as_str).unwrap_or("");
+        return json!({
+            "type": "string",
+            "description": format!("{} Give it as a JSON object encoded in a string.", description)
+                .trim()
+                .to_string()
+        });
+    }
+    if let Some(Value::Object(props)) = map.get_mut("properties") {
+        for prop in props.values_mut() {
+            *prop = gemini_schema(prop.take());
+        }
+    }
+    if let Some(items) = map.get_mut("items") {
+        *items = gemini_schema(items.take());
+    }
+    schema
+}
+
+/// Map Gemini's `finishReason` onto the Anthropic-style stop reasons the loop expects.
+fn stop_reason_for(finish_reason: Option<&str>, has_calls: bool) -> String {
+    if has_calls {
+        return "tool_use".to_string();
+    }
+    match finish_reason {
+        None | Some("STOP") | Some("FINISH_REASON_UNSPECIFIED") => "end_turn",
+        Some("MAX_TOKENS") => "max_tokens",
+        Some("MALFORMED_FUNCTION_CALL") | Some("UNEXPECTED_TOOL_CALL") => "tool_error",
+        // SAFETY, RECITATION, BLOCKLIST, PROHIBITED_CONTENT, SPII, ...
+        Some(_) => "refusal",
+    }
+    .to_string()
+}
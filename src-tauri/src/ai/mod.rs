@@ -1,5 +1,9 @@
 pub mod anthropic;
+pub mod gemini;
 pub mod ollama;
-pub mod openai;
-pub mod openrouter;
+pub mod openai_compat;
+pub mod provider;
+pub mod retry;
+pub mod streaming;
 pub mod types;
+pub mod usage;
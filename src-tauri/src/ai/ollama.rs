@@ -1,282 +1,349 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
 use reqwest::Client;
-use serde_json::json;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
 
-use super::types::{AiResponse, ContentBlock, Message, ToolDefinition};
+use super::openai_compat::{normalize_ollama_base_url, to_chat_tools};
+use super::provider::{AiProvider, ProviderCapabilities, StreamEvent, StreamSink};
+use super::retry::error_from_response;
+use super::streaming::NdjsonReader;
+use super::types::{AiResponse, ContentBlock, Message, ModelOption, ToolDefinition, Usage};
+
+/// Ollama-specific request controls, only honoured by the native `/api/chat` endpoint.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OllamaOptions {
+    /// Talk to `/api/chat` instead of the OpenAI-compatible `/v1` shim.
+    pub native_api: bool,
+    /// Context window in tokens. Ollama's default is small enough that screenshots
+    /// get silently truncated, so this is usually worth raising.
+    pub num_ctx: Option<u32>,
+    pub temperature: Option<f32>,
+    /// Maximum tokens to generate (`-1` for no limit).
+    pub num_predict: Option<i32>,
+    /// How long the model stays loaded after a request, e.g. `"10m"` or `"-1"`.
+    pub keep_alive: String,
+    /// Constrain replies to valid JSON with `format: "json"`.
+    pub json_format: bool,
+}
+
+/// A locally installed model with the metadata reported by `/api/show`.
+#[derive(Debug, Clone, Serialize)]
+pub struct OllamaModel {
+    pub id: String,
+    pub label: String,
+    pub context_length: Option<u64>,
+    /// `None` when the server is too old to report capabilities.
+    pub vision: Option<bool>,
+    pub tool_calling: Option<bool>,
+}
 
 pub struct OllamaClient {
     client: Client,
     api_key: String,
-    model: String,
     base_url: String,
+    model: String,
+    options: OllamaOptions,
 }
 
 impl OllamaClient {
-    pub fn new(api_key: &str, model: &str, base_url: &str) -> Self {
+    pub fn new(api_key: &str, base_url: &str, model: &str, options: OllamaOptions) -> Self {
         Self {
             client: Client::new(),
-            api_key: api_key.to_string(),
+            api_key: api_key.trim().to_string(),
+            base_url: normalize_ollama_base_url(base_url),
             model: model.to_string(),
-            base_url: normalize_base_url(base_url),
+            options,
         }
     }
 
-    pub async fn send(
-        &self,
-        system: &str,
-        messages: &[Message],
-        tools: &[ToolDefinition],
-    ) -> AppResult<AiResponse> {
-        // Convert to OpenAI-compatible message format
-        let mut api_messages: Vec<serde_json::Value> = vec![json!({
-            "role": "system",
-            "content": system
-        })];
-
-        for msg in messages {
-            let mut parts: Vec<serde_json::Value> = Vec::new();
-
-            if msg.role == "user" {
-                let mut tool_results = Vec::new();
-                let mut regular_parts = Vec::new();
-
-                for block in &msg.content {
-                    match block {
-                        ContentBlock::ToolResult {
-                            tool_use_id,
-                            content,
-                            ..
-                        } => {
-                            tool_results.push(json!({
-                                "role": "tool",
-                                "tool_call_id": tool_use_id,
-                                "content": content
-                            }));
-                        }
-                        ContentBlock::Text { text } => {
-                            regular_parts.push(json!({
-                                "type": "text",
-                                "text": text
-                            }));
-                        }
-                        ContentBlock::Image { source } => {
-                            regular_parts.push(json!({
-                                "type": "image_url",
-                                "image_url": {
-                                    "url": format!("data:{};base64,{}", source.media_type, source.data)
-                                }
-                            }));
-                        }
-                        _ => {}
-                    }
-                }
+    fn request(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if self.api_key.is_empty() {
+            builder
+        } else {
+            builder.header("Authorization", format!("Bearer {}", self.api_key))
+        }
+    }
 
-                for tr in tool_results {
-                    api_messages.push(tr);
-                }
+    async fn tags(&self) -> AppResult<Vec<String>> {
+        let resp = self
+            .request(self.client.get(format!("{}/api/tags", self.base_url)))
+            .send()
+            .await?;
 
-                if !regular_parts.is_empty() {
-                    api_messages.push(json!({
-                        "role": "user",
-                        "content": regular_parts
-                    }));
-                }
-                continue;
-            }
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(AppError::AiProvider(format!(
+                "Ollama model fetch failed: HTTP {}",
+                status
+            )));
+        }
 
-            if msg.role == "assistant" {
-                let mut text_parts = Vec::new();
-                let mut tool_calls = Vec::new();
-
-                for block in &msg.content {
-                    match block {
-                        ContentBlock::Text { text } => {
-                            text_parts.push(text.clone());
-                        }
-                        ContentBlock::ToolUse { id, name, input } => {
-                            tool_calls.push(json!({
-                                "id": id,
-                                "type": "function",
-                                "function": {
-                                    "name": name,
-                                    "arguments": serde_json::to_string(input).unwrap_or_default()
-                                }
-                            }));
-                        }
-                        _ => {}
-                    }
-                }
+        let parsed: Value = resp.json().await?;
+        Ok(parsed["models"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|m| m["name"].as_str().or_else(|| m["model"].as_str()))
+            .map(str::to_string)
+            .collect())
+    }
 
-                let mut assistant_msg = json!({
-                    "role": "assistant",
-                    "content": text_parts.join("\n")
-                });
-                if !tool_calls.is_empty() {
-                    assistant_msg["tool_calls"] = json!(tool_calls);
-                }
-                api_messages.push(assistant_msg);
-                continue;
-            }
+    /// Look up a model's context length and capabilities via `/api/show`.
+    pub async fn show(&self, model: &str) -> AppResult<OllamaModel> {
+        let resp = self
+            .request(self.client.post(format!("{}/api/show", self.base_url)))
+            .json(&json!({ "model": model }))
+            .send()
+            .await?;
 
-            for block in &msg.content {
-                match block {
-                    ContentBlock::Text { text } => {
-                        parts.push(json!({
-                            "type": "text",
-                            "text": text
-                        }));
-                    }
-                    ContentBlock::Image { source } => {
-                        parts.push(json!({
-                            "type": "image_url",
-                            "image_url": {
-                                "url": format!("data:{};base64,{}", source.media_type, source.data)
-                            }
-                        }));
+        if !resp.status().is_success() {
+            return Err(error_from_response("Ollama", resp).await);
+        }
+
+        let parsed: Value = resp.json().await?;
+        let context_length = parsed["model_info"].as_object().and_then(|info| {
+            info.iter()
+                .find(|(key, _)| key.ends_with(".context_length"))
+                .and_then(|(_, v)| v.as_u64())
+        });
+        let capabilities = parsed["capabilities"].as_array();
+        let has = |name: &str| capabilities.map(|caps| caps.iter().any(|c| c == name));
+
+        Ok(OllamaModel {
+            id: model.to_string(),
+            label: model.to_string(),
+            context_length,
+            vision: has("vision"),
+            tool_calling: has("tools"),
+        })
+    }
+
+    /// Installed models with their `/api/show` metadata, for the settings UI.
+    pub async fn list_model_details(&self) -> AppResult<Vec<OllamaModel>> {
+        let mut models = Vec::new();
+        for name in self.tags().await? {
+            let model = match self.show(&name).await {
+                Ok(model) => model,
+                Err(e) => {
+                    log::warn!("Failed to read Ollama metadata for {}: {}", name, e);
+                    OllamaModel {
+                        id: name.clone(),
+                        label: name,
+                        context_length: None,
+                        vision: None,
+                        tool_calling: None,
                     }
-                    _ => {}
                 }
-            }
-            if !parts.is_empty() {
-                api_messages.push(json!({
-                    "role": msg.role,
-                    "content": parts
-                }));
-            }
+            };
+            models.push(model);
         }
+        Ok(models)
+    }
+}
 
-        let api_tools: Vec<serde_json::Value> = tools
-            .iter()
-            .filter(|t| t.input_schema.is_some())
-            .map(|tool| {
-                json!({
-                    "type": "function",
-                    "function": {
-                        "name": tool.name,
-                        "description": tool.description.clone().unwrap_or_default(),
-                        "parameters": tool.input_schema.clone().unwrap_or(json!({}))
-                    }
-                })
-            })
-            .collect();
+#[async_trait]
+impl AiProvider for OllamaClient {
+    fn id(&self) -> &str {
+        "ollama"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            native_computer_use: false,
+            vision: true,
+            tool_calling: true,
+        }
+    }
+
+    async fn send(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        on_event: StreamSink<'_>,
+    ) -> AppResult<AiResponse> {
+        let api_tools = to_chat_tools(tools);
 
         let mut body = json!({
             "model": self.model,
-            "messages": api_messages,
-            "stream": false
+            "messages": to_native_messages(system, messages),
+            "stream": true
         });
         if !api_tools.is_empty() {
             body["tools"] = json!(api_tools);
         }
 
-        let mut req = self
-            .client
-            .post(format!("{}/v1/chat/completions", self.base_url))
-            .header("content-type", "application/json");
-        if !self.api_key.trim().is_empty() {
-            req = req.header("Authorization", format!("Bearer {}", self.api_key));
+        let mut options = serde_json::Map::new();
+        if let Some(num_ctx) = self.options.num_ctx {
+            options.insert("num_ctx".to_string(), json!(num_ctx));
+        }
+        if let Some(temperature) = self.options.temperature {
+            options.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(num_predict) = self.options.num_predict {
+            options.insert("num_predict".to_string(), json!(num_predict));
+        }
+        if !options.is_empty() {
+            body["options"] = Value::Object(options);
+        }
+        let keep_alive = self.options.keep_alive.trim();
+        if !keep_alive.is_empty() {
+            // Bare numbers are seconds and must be sent as numbers
+            body["keep_alive"] = match keep_alive.parse::<i64>() {
+                Ok(secs) => json!(secs),
+                Err(_) => json!(keep_alive),
+            };
+        }
+        if self.options.json_format {
+            body["format"] = json!("json");
         }
 
-        let resp = req.json(&body).send().await?;
-        let status = resp.status();
-        let text = resp.text().await?;
+        let resp = self
+            .request(self.client.post(format!("{}/api/chat", self.base_url)))
+            .header("content-type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
 
-        if !status.is_success() {
-            return Err(AppError::AiProvider(format!(
-                "Ollama API error {}: {}",
-                status, text
-            )));
+        if !resp.status().is_success() {
+            return Err(error_from_response("Ollama", resp).await);
         }
 
-        let parsed: serde_json::Value =
-            serde_json::from_str(&text).map_err(|e| AppError::AiProvider(e.to_string()))?;
+        let mut reader = NdjsonReader::new(resp);
+        let mut text = String::new();
+        let mut calls: Vec<ContentBlock> = Vec::new();
+        let mut done_reason: Option<String> = None;
+        let mut usage = Usage::default();
 
-        let choice = &parsed["choices"][0];
-        let message = &choice["message"];
-        let finish_reason = choice["finish_reason"].as_str().unwrap_or("stop");
-
-        let mut content = Vec::new();
+        while let Some(chunk) = reader.next_value().await? {
+            if let Some(error) = chunk["error"].as_str() {
+                return Err(AppError::AiProvider(format!("Ollama API error: {}", error)));
+            }
 
-        if let Some(text) = message["content"].as_str() {
-            if !text.is_empty() {
-                content.push(ContentBlock::Text {
-                    text: text.to_string(),
-                });
+            let message = &chunk["message"];
+            if let Some(piece) = message["thinking"].as_str() {
+                if !piece.is_empty() {
+                    on_event(StreamEvent::ThinkingDelta(piece.to_string()));
+                }
             }
-        } else if let Some(arr) = message["content"].as_array() {
-            let joined = arr
-                .iter()
-                .filter_map(|part| part["text"].as_str())
-                .collect::<Vec<_>>()
-                .join("\n");
-            if !joined.is_empty() {
-                content.push(ContentBlock::Text { text: joined });
+            if let Some(piece) = message["content"].as_str() {
+                if !piece.is_empty() {
+                    text.push_str(piece);
+                    on_event(StreamEvent::TextDelta(piece.to_string()));
+                }
+            }
+            for call in message["tool_calls"].as_array().into_iter().flatten() {
+                calls.push(ContentBlock::ToolUse {
+                    id: format!("call_{}", Uuid::new_v4()),
+                    name: call["function"]["name"].as_str().unwrap_or("").to_string(),
+                    input: call["function"]
+                        .get("arguments")
+                        .cloned()
+                        .unwrap_or(json!({})),
+                });
             }
-        }
 
-        if let Some(tool_calls) = message["tool_calls"].as_array() {
-            for tc in tool_calls {
-                let id = tc["id"]
-                    .as_str()
-                    .map(str::to_string)
-                    .filter(|s| !s.is_empty())
-                    .unwrap_or_else(|| format!("tool_{}", Uuid::new_v4()));
-                let name = tc["function"]["name"].as_str().unwrap_or("").to_string();
-                let args = &tc["function"]["arguments"];
-
-                let input = if let Some(args_str) = args.as_str() {
-                    serde_json::from_str(args_str).unwrap_or(json!({}))
-                } else if args.is_object() {
-                    args.clone()
-                } else {
-                    json!({})
-                };
-
-                content.push(ContentBlock::ToolUse { id, name, input });
+            if chunk["done"].as_bool() == Some(true) {
+                done_reason = chunk["done_reason"].as_str().map(str::to_string);
+                usage.input_tokens = chunk["prompt_eval_count"].as_u64().unwrap_or(0);
+                usage.output_tokens = chunk["eval_count"].as_u64().unwrap_or(0);
+                break;
             }
         }
 
-        let stop_reason = if finish_reason == "tool_calls"
-            || message["tool_calls"]
-                .as_array()
-                .map(|arr| !arr.is_empty())
-                .unwrap_or(false)
-        {
-            "tool_use".to_string()
+        let stop_reason = if !calls.is_empty() {
+            "tool_use"
+        } else if done_reason.as_deref() == Some("length") {
+            "max_tokens"
         } else {
-            "end_turn".to_string()
+            "end_turn"
         };
 
+        let mut content = Vec::new();
+        if !text.is_empty() {
+            content.push(ContentBlock::Text { text });
+        }
+        content.extend(calls);
+
         Ok(AiResponse {
             content,
-            stop_reason,
+            stop_reason: stop_reason.to_string(),
+            usage,
         })
     }
-}
 
-fn normalize_base_url(input: &str) -> String {
-    let trimmed = input.trim();
-    let mut url = if trimmed.is_empty() {
-        "http://127.0.0.1:11434".to_string()
-    } else if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
-        trimmed.to_string()
-    } else {
-        format!("http://{}", trimmed)
-    };
-
-    while url.ends_with('/') {
-        url.pop();
+    async fn list_models(&self) -> AppResult<Vec<ModelOption>> {
+        Ok(self
+            .tags()
+            .await?
+            .into_iter()
+            .map(|name| ModelOption {
+                id: name.clone(),
+                label: name,
+            })
+            .collect())
     }
+}
 
-    if url.ends_with("/v1") {
-        url.truncate(url.len() - 3);
-    }
+/// Convert the conversation to `/api/chat` messages, with images as base64 arrays
+/// and tool results as `tool` messages.
+fn to_native_messages(system: &str, messages: &[Message]) -> Vec<Value> {
+    let mut api_messages = vec![json!({ "role": "system", "content": system })];
+    // Native tool calls carry no ids, so results are tied back by tool name
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+
+    for msg in messages {
+        let mut text_parts: Vec<&str> = Vec::new();
+        let mut images: Vec<&str> = Vec::new();
+        let mut tool_calls: Vec<Value> = Vec::new();
+
+        for block in &msg.content {
+            match block {
+                ContentBlock::Text { text } => text_parts.push(text),
+                ContentBlock::Image { source } => images.push(&source.data),
+                ContentBlock::ToolUse { id, name, input } => {
+                    tool_names.insert(id, name);
+                    tool_calls.push(json!({
+                        "function": { "name": name, "arguments": input }
+                    }));
+                }
+                ContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    ..
+                } => {
+                    api_messages.push(json!({
+                        "role": "tool",
+                        "content": content,
+                        "tool_name": tool_names.get(tool_use_id.as_str()).copied().unwrap_or("")
+                    }));
+                }
+                ContentBlock::Thinking { .. } | ContentBlock::RedactedThinking { .. } => {}
+            }
+        }
 
-    while url.ends_with('/') {
-        url.pop();
+        if text_parts.is_empty() && images.is_empty() && tool_calls.is_empty() {
+            continue;
+        }
+
+        let mut api_msg = json!({
+            "role": msg.role,
+            "content": text_parts.join("\n")
+        });
+        if !images.is_empty() {
+            api_msg["images"] = json!(images);
+        }
+        if !tool_calls.is_empty() {
+            api_msg["tool_calls"] = json!(tool_calls);
+        }
+        api_messages.push(api_msg);
     }
 
-    url
+    api_messages
 }
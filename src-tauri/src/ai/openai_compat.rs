@@ -0,0 +1,414 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::error::{AppError, AppResult};
+
+use super::provider::{AiProvider, ProviderCapabilities, StreamSink};
+use super::retry::error_from_response;
+use super::streaming::read_chat_completion_stream;
+use super::types::{AiResponse, ContentBlock, Message, ModelOption, ToolDefinition};
+
+const MAX_OUTPUT_TOKENS: u32 = 4096;
+
+/// How the API key is attached to requests.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AuthScheme {
+    /// `Authorization: Bearer <key>`
+    #[default]
+    Bearer,
+    /// The raw key in a custom header, e.g. `api-key` for Azure-style gateways.
+    Header {
+        name: String,
+    },
+    None,
+}
+
+/// Which request field carries the output token limit.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenLimitField {
+    #[default]
+    MaxTokens,
+    MaxCompletionTokens,
+    /// Leave the limit to the server's default.
+    Omit,
+}
+
+/// Connection details for any server speaking the OpenAI chat completions API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderProfile {
+    pub id: String,
+    pub label: String,
+    /// Base URL up to and including the version segment, e.g. `http://localhost:1234/v1`.
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default)]
+    pub auth: AuthScheme,
+    #[serde(default)]
+    pub extra_headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub token_limit_field: TokenLimitField,
+}
+
+impl ProviderProfile {
+    pub fn openai(api_key: &str) -> Self {
+        Self {
+            id: "openai".to_string(),
+            label: "OpenAI".to_string(),
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key: api_key.to_string(),
+            auth: AuthScheme::Bearer,
+            extra_headers: BTreeMap::new(),
+            token_limit_field: TokenLimitField::MaxCompletionTokens,
+        }
+    }
+
+    pub fn openrouter(api_key: &str) -> Self {
+        let mut extra_headers = BTreeMap::new();
+        extra_headers.insert("HTTP-Referer".to_string(), "delegant".to_string());
+        extra_headers.insert("X-Title".to_string(), "Delegant".to_string());
+        Self {
+            id: "openrouter".to_string(),
+            label: "OpenRouter".to_string(),
+            base_url: "https://openrouter.ai/api/v1".to_string(),
+            api_key: api_key.to_string(),
+            auth: AuthScheme::Bearer,
+            extra_headers,
+            token_limit_field: TokenLimitField::MaxCompletionTokens,
+        }
+    }
+
+    pub fn ollama(api_key: &str, base_url: &str) -> Self {
+        Self {
+            id: "ollama".to_string(),
+            label: "Ollama".to_string(),
+            base_url: format!("{}/v1", normalize_ollama_base_url(base_url)),
+            api_key: api_key.to_string(),
+            auth: AuthScheme::Bearer,
+            extra_headers: BTreeMap::new(),
+            token_limit_field: TokenLimitField::Omit,
+        }
+    }
+}
+
+pub struct OpenAiCompatClient {
+    client: Client,
+    profile: ProviderProfile,
+    model: String,
+    /// `low`/`medium`/`high`, sent only to reasoning models; empty uses the server default.
+    reasoning_effort: String,
+}
+
+impl OpenAiCompatClient {
+    pub fn new(profile: ProviderProfile, model: &str) -> Self {
+        let mut profile = profile;
+        while profile.base_url.ends_with('/') {
+            profile.base_url.pop();
+        }
+        Self {
+            client: Client::new(),
+            profile,
+            model: model.to_string(),
+            reasoning_effort: String::new(),
+        }
+    }
+
+    pub fn with_reasoning_effort(mut self, effort: &str) -> Self {
+        self.reasoning_effort = effort.trim().to_lowercase();
+        self
+    }
+
+    fn request(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let key = self.profile.api_key.trim();
+        let mut builder = match &self.profile.auth {
+            // Local servers usually run without a key, so skip the header entirely
+            _ if key.is_empty() => builder,
+            AuthScheme::Bearer => builder.header("Authorization", format!("Bearer {}", key)),
+            AuthScheme::Header { name } => builder.header(name.as_str(), key),
+            AuthScheme::None => builder,
+        };
+        for (name, value) in &self.profile.extra_headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        builder
+    }
+}
+
+#[async_trait]
+impl AiProvider for OpenAiCompatClient {
+    fn id(&self) -> &str {
+        &self.profile.id
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            native_computer_use: false,
+            vision: true,
+            tool_calling: true,
+        }
+    }
+
+    async fn send(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        on_event: StreamSink<'_>,
+    ) -> AppResult<AiResponse> {
+        let api_messages = to_chat_messages(system, messages);
+        let api_tools = to_chat_tools(tools);
+
+        let mut body = json!({
+            "model": self.model,
+            "messages": api_messages,
+            "stream": true,
+            "stream_options": { "include_usage": true }
+        });
+        match self.profile.token_limit_field {
+            TokenLimitField::MaxTokens => body["max_tokens"] = json!(MAX_OUTPUT_TOKENS),
+            TokenLimitField::MaxCompletionTokens => {
+                body["max_completion_tokens"] = json!(MAX_OUTPUT_TOKENS)
+            }
+            TokenLimitField::Omit => {}
+        }
+        if !api_tools.is_empty() {
+            body["tools"] = json!(api_tools);
+        }
+        if !self.reasoning_effort.is_empty() && is_reasoning_model(&self.model) {
+            body["reasoning_effort"] = json!(self.reasoning_effort);
+        }
+
+        let resp = self
+            .request(
+                self.client
+                    .post(format!("{}/chat/completions", self.profile.base_url)),
+            )
+            .header("content-type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(error_from_response(&self.profile.label, resp).await);
+        }
+
+        read_chat_completion_stream(resp, &self.profile.label, on_event).await
+    }
+
+    async fn list_models(&self) -> AppResult<Vec<ModelOption>> {
+        let resp = self
+            .request(self.client.get(format!("{}/models", self.profile.base_url)))
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(AppError::AiProvider(format!(
+                "{} model fetch failed: HTTP {}",
+                self.profile.label, status
+            )));
+        }
+
+        let parsed: serde_json::Value = resp.json().await?;
+        let mut models = parsed["data"].as_array().cloned().unwrap_or_default();
+        models.sort_by(|a, b| {
+            b["created"]
+                .as_i64()
+                .unwrap_or(0)
+                .cmp(&a["created"].as_i64().unwrap_or(0))
+        });
+
+        Ok(models
+            .iter()
+            .filter_map(|m| {
+                let id = m["id"].as_str()?;
+                let label = m["name"].as_str().unwrap_or(id);
+                Some(ModelOption {
+                    id: id.to_string(),
+                    label: label.to_string(),
+                })
+            })
+            .collect())
+    }
+}
+
+/// Convert the conversation to OpenAI chat format, with tool results as `tool` messages.
+fn to_chat_messages(system: &str, messages: &[Message]) -> Vec<serde_json::Value> {
+    let mut api_messages: Vec<serde_json::Value> = vec![json!({
+        "role": "system",
+        "content": system
+    })];
+
+    for msg in messages {
+        let mut parts: Vec<serde_json::Value> = Vec::new();
+
+        // Handle tool_result messages specially
+        if msg.role == "user" {
+            let mut tool_results = Vec::new();
+            let mut regular_parts = Vec::new();
+
+            for block in &msg.content {
+                match block {
+                    ContentBlock::ToolResult {
+                        tool_use_id,
+                        content,
+                        ..
+                    } => {
+                        tool_results.push(json!({
+                            "role": "tool",
+                            "tool_call_id": tool_use_id,
+                            "content": content
+                        }));
+                    }
+                    ContentBlock::Text { text } => {
+                        regular_parts.push(json!({
+                            "type": "text",
+                            "text": text
+                        }));
+                    }
+                    ContentBlock::Image { source } => {
+                        regular_parts.push(json!({
+                            "type": "image_url",
+                            "image_url": {
+                                "url": format!("data:{};base64,{}", source.media_type, source.data)
+                            }
+                        }));
+                    }
+                    _ => {}
+                }
+            }
+
+            // Add tool results first
+            for tr in tool_results {
+                api_messages.push(tr);
+            }
+
+            // Then regular content if any
+            if !regular_parts.is_empty() {
+                api_messages.push(json!({
+                    "role": "user",
+                    "content": regular_parts
+                }));
+            }
+            continue;
+        }
+
+        if msg.role == "assistant" {
+            let mut text_parts = Vec::new();
+            let mut tool_calls = Vec::new();
+
+            for block in &msg.content {
+                match block {
+                    ContentBlock::Text { text } => {
+                        text_parts.push(text.clone());
+                    }
+                    ContentBlock::ToolUse { id, name, input } => {
+                        tool_calls.push(json!({
+                            "id": id,
+                            "type": "function",
+                            "function": {
+                                "name": name,
+                                "arguments": serde_json::to_string(input).unwrap_or_default()
+                            }
+                        }));
+                    }
+                    _ => {}
+                }
+            }
+
+            let mut assistant_msg = json!({
+                "role": "assistant",
+                "content": text_parts.join("\n")
+            });
+            if !tool_calls.is_empty() {
+                assistant_msg["tool_calls"] = json!(tool_calls);
+            }
+            api_messages.push(assistant_msg);
+            continue;
+        }
+
+        for block in &msg.content {
+            match block {
+                ContentBlock::Text { text } => {
+                    parts.push(json!({
+                        "type": "text",
+                        "text": text
+                    }));
+                }
+                ContentBlock::Image { source } => {
+                    parts.push(json!({
+                        "type": "image_url",
+                        "image_url": {
+                            "url": format!("data:{};base64,{}", source.media_type, source.data)
+                        }
+                    }));
+                }
+                _ => {}
+            }
+        }
+        if !parts.is_empty() {
+            api_messages.push(json!({
+                "role": msg.role,
+                "content": parts
+            }));
+        }
+    }
+
+    api_messages
+}
+
+/// Whether `model` is an o-series reasoning model (`o1`, `o3-mini`, `openai/o4-mini`, ...).
+fn is_reasoning_model(model: &str) -> bool {
+    let name = model.rsplit('/').next().unwrap_or(model);
+    let mut chars = name.chars();
+    chars.next() == Some('o') && chars.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Convert tool definitions to OpenAI function format.
+pub fn to_chat_tools(tools: &[ToolDefinition]) -> Vec<serde_json::Value> {
+    tools
+        .iter()
+        .filter(|t| t.input_schema.is_some())
+        .map(|tool| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description.clone().unwrap_or_default(),
+                    "parameters": tool.input_schema.clone().unwrap_or(json!({}))
+                }
+            })
+        })
+        .collect()
+}
+
+pub fn normalize_ollama_base_url(input: &str) -> String {
+    let trimmed = input.trim();
+    let mut url = if trimmed.is_empty() {
+        "http://127.0.0.1:11434".to_string()
+    } else if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
+        trimmed.to_string()
+    } else {
+        format!("http://{}", trimmed)
+    };
+
+    while url.ends_with('/') {
+        url.pop();
+    }
+
+    if url.ends_with("/v1") {
+        url.truncate(url.len() - 3);
+    }
+
+    while url.ends_with('/') {
+        url.pop();
+    }
+
+    url
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::state::Settings;
+
+use super::anthropic::AnthropicClient;
+use super::gemini::GeminiClient;
+use super::ollama::OllamaClient;
+use super::openai_compat::{OpenAiCompatClient, ProviderProfile};
+use super::types::{AiResponse, Message, ModelOption, ToolDefinition};
+
+/// What a backend supports, so callers can adapt without matching on provider ids.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderCapabilities {
+    /// Accepts Anthropic's built-in `computer`/`bash`/`text_editor` tool types.
+    pub native_computer_use: bool,
+    pub vision: bool,
+    pub tool_calling: bool,
+}
+
+/// Incremental output surfaced while a response is still streaming.
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    TextDelta(String),
+    ThinkingDelta(String),
+}
+
+pub type StreamSink<'a> = &'a (dyn Fn(StreamEvent) + Send + Sync);
+
+#[async_trait]
+pub trait AiProvider: Send + Sync {
+    fn id(&self) -> &str;
+
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    /// Stream a completion, reporting deltas through `on_event` as they arrive.
+    async fn send(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        on_event: StreamSink<'_>,
+    ) -> AppResult<AiResponse>;
+
+    async fn list_models(&self) -> AppResult<Vec<ModelOption>>;
+}
+
+pub type ProviderFactory = fn(&Settings) -> Arc<dyn AiProvider>;
+
+/// Maps provider ids (as stored in `Settings.provider`) to client constructors.
+pub struct ProviderRegistry {
+    factories: HashMap<String, ProviderFactory>,
+}
+
+impl ProviderRegistry {
+    pub fn new() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Registry with every backend that ships with Delegant.
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register("anthropic", |s| {
+            Arc::new(AnthropicClient::new(
+                &s.anthropic_api_key,
+                &s.model,
+                s.thinking_budget_tokens,
+            ))
+        });
+        registry.register("gemini", |s| {
+            Arc::new(GeminiClient::new(
+                &s.gemini_api_key,
+                &s.gemini_base_url,
+                &s.model,
+                s.thinking_budget_tokens,
+            ))
+        });
+        registry.register("openai", |s| {
+            Arc::new(
+                OpenAiCompatClient::new(ProviderProfile::openai(&s.openai_api_key), &s.model)
+                    .with_reasoning_effort(&s.reasoning_effort),
+            )
+        });
+        registry.register("openrouter", |s| {
+            Arc::new(OpenAiCompatClient::new(
+                ProviderProfile::openrouter(&s.openrouter_api_key),
+                &s.model,
+            ))
+        });
+        registry.register("ollama", |s| {
+            if s.ollama_options.native_api {
+                return Arc::new(OllamaClient::new(
+                    &s.ollama_api_key,
+                    &s.ollama_base_url,
+                    &s.model,
+                    s.ollama_options.clone(),
+                ));
+            }
+            Arc::new(OpenAiCompatClient::new(
+                ProviderProfile::ollama(&s.ollama_api_key, &s.ollama_base_url),
+                &s.model,
+            ))
+        });
+        registry
+    }
+
+    pub fn register(&mut self, id: &str, factory: ProviderFactory) {
+        self.factories.insert(id.to_string(), factory);
+    }
+
+    /// Whether `id` names a registered backend or one of the user's custom profiles.
+    pub fn supports(&self, id: &str, settings: &Settings) -> bool {
+        self.factories.contains_key(id) || settings.custom_providers.iter().any(|p| p.id == id)
+    }
+
+    /// Build a client for `id` from the given settings.
+    pub fn create(&self, id: &str, settings: &Settings) -> AppResult<Arc<dyn AiProvider>> {
+        if let Some(factory) = self.factories.get(id) {
+            return Ok(factory(settings));
+        }
+        settings
+            .custom_providers
+            .iter()
+            .find(|p| p.id == id)
+            .map(|profile| {
+                Arc::new(OpenAiCompatClient::new(profile.clone(), &settings.model))
+                    as Arc<dyn AiProvider>
+            })
+            .ok_or_else(|| AppError::Settings(format!("Unknown provider: {}", id)))
+    }
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        Self::with_builtin()
+    }
+}
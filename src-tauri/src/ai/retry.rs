@@ -0,0 +1,164 @@
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::header::HeaderMap;
+use reqwest::{Response, StatusCode};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::state::Settings;
+
+const BASE_DELAY: Duration = Duration::from_millis(1000);
+
+/// Anthropic rate limit families, each with `-remaining` and `-reset` headers.
+const ANTHROPIC_RATE_LIMITS: [&str; 4] = ["requests", "tokens", "input-tokens", "output-tokens"];
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            max_attempts: settings.retry_max_attempts.max(1),
+            max_delay: Duration::from_secs(settings.retry_max_delay_secs.max(1)),
+        }
+    }
+
+    /// Jittered exponential backoff for the given (1-based) attempt.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exp = BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        let capped = exp.min(self.max_delay);
+        capped.mul_f64(rand::rng().random_range(0.5..=1.0))
+    }
+}
+
+/// Emitted to the UI before sleeping ahead of another attempt.
+#[derive(Debug, Clone, Serialize)]
+pub struct RetryNotice {
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub delay_ms: u64,
+    pub error: String,
+}
+
+/// Whether an error is worth retrying, and how long the server asked us to wait.
+fn classify(err: &AppError) -> Option<Option<Duration>> {
+    match err {
+        AppError::ProviderHttp {
+            status,
+            retry_after,
+            ..
+        } => {
+            let retryable = *status == StatusCode::TOO_MANY_REQUESTS
+                || *status == StatusCode::REQUEST_TIMEOUT
+                || status.is_server_error()
+                || status.as_u16() == 529;
+            retryable.then_some(*retry_after)
+        }
+        AppError::Request(e) => {
+            (e.is_connect() || e.is_timeout() || e.is_body() || e.is_request()).then_some(None)
+        }
+        _ => None,
+    }
+}
+
+/// Run `op` until it succeeds, a non-retryable error occurs, or attempts run out.
+pub async fn with_retry<T, F, Fut>(
+    policy: &RetryPolicy,
+    mut op: F,
+    mut on_retry: impl FnMut(&RetryNotice),
+) -> AppResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = AppResult<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        let err = match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+
+        let retry_after = match classify(&err) {
+            Some(retry_after) if attempt < policy.max_attempts => retry_after,
+            _ => return Err(err),
+        };
+
+        let delay = match retry_after {
+            Some(wait) => wait.min(policy.max_delay),
+            None => policy.backoff(attempt),
+        };
+
+        log::warn!(
+            "Provider request failed (attempt {}/{}), retrying in {:?}: {}",
+            attempt,
+            policy.max_attempts,
+            delay,
+            err
+        );
+        on_retry(&RetryNotice {
+            attempt,
+            max_attempts: policy.max_attempts,
+            delay_ms: delay.as_millis() as u64,
+            error: err.to_string(),
+        });
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Turn a non-2xx provider response into an error carrying any server-requested delay.
+pub async fn error_from_response(provider: &str, resp: Response) -> AppError {
+    let status = resp.status();
+    let retry_after = retry_after_from_headers(resp.headers());
+    let body = match resp.text().await {
+        Ok(text) => text,
+        Err(e) => return AppError::Request(e),
+    };
+
+    AppError::ProviderHttp {
+        provider: provider.to_string(),
+        status,
+        body,
+        retry_after,
+    }
+}
+
+fn retry_after_from_headers(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(value) = header("retry-after") {
+        // `inf` and `NaN` parse as floats but mean nothing here. Values too large for
+        // a `Duration` saturate; `with_retry` caps every wait at the policy's max delay.
+        if let Ok(secs) = value.trim().parse::<f64>() {
+            if secs.is_finite() {
+                return Some(Duration::try_from_secs_f64(secs.max(0.0)).unwrap_or(Duration::MAX));
+            }
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+            return Some(until(date.with_timezone(&chrono::Utc)));
+        }
+    }
+
+    // Wait for the latest reset among the limits that are actually exhausted
+    ANTHROPIC_RATE_LIMITS
+        .iter()
+        .filter(|limit| {
+            header(&format!("anthropic-ratelimit-{}-remaining", limit))
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                == Some(0)
+        })
+        .filter_map(|limit| header(&format!("anthropic-ratelimit-{}-reset", limit)))
+        .filter_map(|v| chrono::DateTime::parse_from_rfc3339(v.trim()).ok())
+        .map(|date| until(date.with_timezone(&chrono::Utc)))
+        .max()
+}
+
+fn until(at: chrono::DateTime<chrono::Utc>) -> Duration {
+    (at - chrono::Utc::now()).to_std().unwrap_or(Duration::ZERO)
+}
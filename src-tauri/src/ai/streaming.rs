@@ -0,0 +1,299 @@
+use reqwest::Response;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+use super::provider::{StreamEvent, StreamSink};
+use super::types::{AiResponse, ContentBlock, Usage};
+
+/// A single server-sent event.
+#[derive(Debug, Clone)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Incrementally reads `text/event-stream` bodies chunk by chunk.
+///
+/// Dropping the reader (e.g. when the surrounding future is cancelled) drops the
+/// response and closes the connection, which aborts generation mid-flight.
+pub struct SseReader {
+    resp: Response,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl SseReader {
+    pub fn new(resp: Response) -> Self {
+        Self {
+            resp,
+            buffer: Vec::new(),
+            done: false,
+        }
+    }
+
+    pub async fn next_event(&mut self) -> AppResult<Option<SseEvent>> {
+        loop {
+            if let Some(event) = self.take_event() {
+                return Ok(Some(event));
+            }
+            if self.done {
+                // Flush a trailing event that wasn't terminated by a blank line
+                let rest = std::mem::take(&mut self.buffer);
+                return Ok(parse_event(&String::from_utf8_lossy(&rest)));
+            }
+            match self.resp.chunk().await? {
+                // Line endings may be CRLF; raw CRs never appear inside JSON payloads
+                Some(bytes) => self.buffer.extend(bytes.iter().filter(|b| **b != b'\r')),
+                None => self.done = true,
+            }
+        }
+    }
+
+    fn take_event(&mut self) -> Option<SseEvent> {
+        while let Some(pos) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..pos + 2).collect();
+            if let Some(event) = parse_event(&String::from_utf8_lossy(&block)) {
+                return Some(event);
+            }
+        }
+        None
+    }
+}
+
+fn parse_event(block: &str) -> Option<SseEvent> {
+    let mut event = None;
+    let mut data: Vec<&str> = Vec::new();
+
+    for line in block.lines() {
+        if line.is_empty() || line.starts_with(':') {
+            continue;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => event = Some(value.to_string()),
+            "data" => data.push(value),
+            _ => {}
+        }
+    }
+
+    if event.is_none() && data.is_empty() {
+        return None;
+    }
+
+    Some(SseEvent {
+        event,
+        data: data.join("\n"),
+    })
+}
+
+/// Incrementally reads newline-delimited JSON bodies, as streamed by Ollama's native API.
+pub struct NdjsonReader {
+    resp: Response,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl NdjsonReader {
+    pub fn new(resp: Response) -> Self {
+        Self {
+            resp,
+            buffer: Vec::new(),
+            done: false,
+        }
+    }
+
+    pub async fn next_value(&mut self) -> AppResult<Option<Value>> {
+        loop {
+            while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+                if let Some(value) = parse_json_line(&line)? {
+                    return Ok(Some(value));
+                }
+            }
+            if self.done {
+                let rest = std::mem::take(&mut self.buffer);
+                return parse_json_line(&rest);
+            }
+            match self.resp.chunk().await? {
+                Some(bytes) => self.buffer.extend_from_slice(&bytes),
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+fn parse_json_line(line: &[u8]) -> AppResult<Option<Value>> {
+    let text = String::from_utf8_lossy(line);
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(text)
+        .map(Some)
+        .map_err(|e| AppError::AiProvider(format!("Stream parse error: {}", e)))
+}
+
+struct PartialToolCall {
+    index: usize,
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// Assembles OpenAI-compatible `chat.completion.chunk` deltas into a full response.
+#[derive(Default)]
+pub struct ChatCompletionAccumulator {
+    text: String,
+    tool_calls: Vec<PartialToolCall>,
+    finish_reason: Option<String>,
+    usage: Usage,
+}
+
+impl ChatCompletionAccumulator {
+    /// Apply one chunk, returning the text delta it carried (if any).
+    pub fn push_chunk(&mut self, chunk: &Value) -> Option<String> {
+        // Sent on the final chunk when `stream_options.include_usage` is set
+        let usage = &chunk["usage"];
+        if usage.is_object() {
+            let prompt = usage["prompt_tokens"].as_u64().unwrap_or(0);
+            let cached = usage["prompt_tokens_details"]["cached_tokens"]
+                .as_u64()
+                .unwrap_or(0);
+            self.usage = Usage {
+                input_tokens: prompt.saturating_sub(cached),
+                output_tokens: usage["completion_tokens"].as_u64().unwrap_or(0),
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: cached,
+            };
+        }
+
+        let choice = &chunk["choices"][0];
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            self.finish_reason = Some(reason.to_string());
+        }
+
+        let delta = &choice["delta"];
+
+        if let Some(calls) = delta["tool_calls"].as_array() {
+            for tc in calls {
+                // Some servers (e.g. Ollama) omit `index` and send whole calls at once
+                let index = tc["index"]
+                    .as_u64()
+                    .map(|i| i as usize)
+                    .unwrap_or(self.tool_calls.len());
+
+                let pos = match self.tool_calls.iter().position(|c| c.index == index) {
+                    Some(pos) => pos,
+                    None => {
+                        self.tool_calls.push(PartialToolCall {
+                            index,
+                            id: String::new(),
+                            name: String::new(),
+                            arguments: String::new(),
+                        });
+                        self.tool_calls.len() - 1
+                    }
+                };
+                let call = &mut self.tool_calls[pos];
+
+                if let Some(id) = tc["id"].as_str() {
+                    call.id.push_str(id);
+                }
+                if let Some(name) = tc["function"]["name"].as_str() {
+                    call.name.push_str(name);
+                }
+                let args = &tc["function"]["arguments"];
+                if let Some(fragment) = args.as_str() {
+                    call.arguments.push_str(fragment);
+                } else if args.is_object() {
+                    call.arguments = args.to_string();
+                }
+            }
+        }
+
+        match delta["content"].as_str() {
+            Some(text) if !text.is_empty() => {
+                self.text.push_str(text);
+                Some(text.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn finish(self) -> AiResponse {
+        let mut content = Vec::new();
+
+        if !self.text.is_empty() {
+            content.push(ContentBlock::Text { text: self.text });
+        }
+
+        let has_tool_calls = !self.tool_calls.is_empty();
+        for call in self.tool_calls {
+            let id = if call.id.is_empty() {
+                format!("tool_{}", Uuid::new_v4())
+            } else {
+                call.id
+            };
+            let input = if call.arguments.trim().is_empty() {
+                json!({})
+            } else {
+                serde_json::from_str(&call.arguments).unwrap_or(json!({}))
+            };
+            content.push(ContentBlock::ToolUse {
+                id,
+                name: call.name,
+                input,
+            });
+        }
+
+        let stop_reason = if has_tool_calls || self.finish_reason.as_deref() == Some("tool_calls") {
+            "tool_use".to_string()
+        } else {
+            "end_turn".to_string()
+        };
+
+        AiResponse {
+            content,
+            stop_reason,
+            usage: self.usage,
+        }
+    }
+}
+
+/// Read an OpenAI-compatible streaming chat completion to the end, forwarding text deltas.
+pub async fn read_chat_completion_stream(
+    resp: Response,
+    provider_name: &str,
+    on_event: StreamSink<'_>,
+) -> AppResult<AiResponse> {
+    let mut reader = SseReader::new(resp);
+    let mut acc = ChatCompletionAccumulator::default();
+
+    while let Some(event) = reader.next_event().await? {
+        if event.data == "[DONE]" {
+            break;
+        }
+
+        let chunk: Value = serde_json::from_str(&event.data).map_err(|e| {
+            AppError::AiProvider(format!("{} stream parse error: {}", provider_name, e))
+        })?;
+
+        if !chunk["error"].is_null() {
+            return Err(AppError::AiProvider(format!(
+                "{} API error: {}",
+                provider_name, chunk["error"]
+            )));
+        }
+
+        if let Some(text) = acc.push_chunk(&chunk) {
+            on_event(StreamEvent::TextDelta(text));
+        }
+    }
+
+    Ok(acc.finish())
+}
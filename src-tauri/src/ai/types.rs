@@ -28,6 +28,12 @@ pub enum ContentBlock {
         #[serde(skip_serializing_if = "Option::is_none")]
         is_error: Option<bool>,
     },
+    /// Model reasoning; must be sent back unchanged (signature included) on later turns.
+    #[serde(rename = "thinking")]
+    Thinking { thinking: String, signature: String },
+    /// Reasoning the provider encrypted for safety reasons; only `data` is returned.
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,8 +61,40 @@ pub struct ToolDefinition {
     pub display_number: Option<u32>,
 }
 
+/// Token counts reported by the provider for a single request.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+}
+
+impl Usage {
+    pub fn add(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AiResponse {
     pub content: Vec<ContentBlock>,
     pub stop_reason: String,
+    pub usage: Usage,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelOption {
+    pub id: String,
+    pub label: String,
 }
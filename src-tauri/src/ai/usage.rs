@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::types::Usage;
+
+/// USD prices per million tokens.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    #[serde(default)]
+    pub cache_write: f64,
+    #[serde(default)]
+    pub cache_read: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_creation_input_tokens as f64 * self.cache_write
+            + usage.cache_read_input_tokens as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// Built-in price table; entries in `Settings.model_prices` take precedence.
+pub fn default_model_prices() -> HashMap<String, ModelPrice> {
+    let price = |input, output, cache_write, cache_read| ModelPrice {
+        input,
+        output,
+        cache_write,
+        cache_read,
+    };
+    HashMap::from([
+        ("claude-opus-4".to_string(), price(15.0, 75.0, 18.75, 1.5)),
+        ("claude-opus-4-5".to_string(), price(5.0, 25.0, 6.25, 0.5)),
+        ("claude-opus-4-6".to_string(), price(5.0, 25.0, 6.25, 0.5)),
+        ("claude-sonnet-4".to_string(), price(3.0, 15.0, 3.75, 0.3)),
+        ("claude-haiku-4-5".to_string(), price(1.0, 5.0, 1.25, 0.1)),
+        ("gemini-2.5-pro".to_string(), price(1.25, 10.0, 0.0, 0.31)),
+        ("gemini-2.5-flash".to_string(), price(0.3, 2.5, 0.0, 0.075)),
+        (
+            "gemini-2.5-flash-lite".to_string(),
+            price(0.1, 0.4, 0.0, 0.025),
+        ),
+        ("gpt-4.1".to_string(), price(2.0, 8.0, 0.0, 0.5)),
+        ("gpt-4.1-mini".to_string(), price(0.4, 1.6, 0.0, 0.1)),
+        ("gpt-4.1-nano".to_string(), price(0.1, 0.4, 0.0, 0.025)),
+        ("o3".to_string(), price(2.0, 8.0, 0.0, 0.5)),
+        ("o4-mini".to_string(), price(1.1, 4.4, 0.0, 0.275)),
+    ])
+}
+
+/// Find the price for a model id, preferring the longest matching table key so
+/// dated ids (`claude-sonnet-4-20250514`) and routed ids (`anthropic/claude-sonnet-4.6`)
+/// resolve without listing every variant.
+fn lookup_price<'a>(
+    prices: &'a HashMap<String, ModelPrice>,
+    model: &str,
+) -> Option<&'a ModelPrice> {
+    if let Some(price) = prices.get(model) {
+        return Some(price);
+    }
+    let normalized = model.replace('.', "-");
+    prices
+        .iter()
+        .filter(|(key, _)| normalized.contains(&key.replace('.', "-")))
+        .max_by_key(|(key, _)| key.len())
+        .map(|(_, price)| price)
+}
+
+/// Price for `model`, checking the user's table before the built-in one.
+pub fn price_for(overrides: &HashMap<String, ModelPrice>, model: &str) -> Option<ModelPrice> {
+    lookup_price(overrides, model)
+        .copied()
+        .or_else(|| lookup_price(&default_model_prices(), model).copied())
+}
+
+/// Running totals for one agent run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunUsage {
+    #[serde(flatten)]
+    pub tokens: Usage,
+    pub requests: u32,
+    /// `None` when the model has no entry in the price table.
+    pub cost_usd: Option<f64>,
+}
+
+impl RunUsage {
+    pub fn record(&mut self, usage: &Usage, price: Option<&ModelPrice>) {
+        self.tokens.add(usage);
+        self.requests += 1;
+        if let Some(price) = price {
+            *self.cost_usd.get_or_insert(0.0) += price.cost(usage);
+        }
+    }
+
+    /// Describe the first budget this run has exceeded, if any. Zero disables a limit.
+    pub fn exceeded_budget(&self, max_tokens: u64, max_cost_usd: f64) -> Option<String> {
+        let total = self.tokens.total();
+        if max_tokens > 0 && total > max_tokens {
+            return Some(format!(
+                "Token budget exceeded: used {} of {} tokens allowed for this task.",
+                total, max_tokens
+            ));
+        }
+        if let Some(cost) = self.cost_usd {
+            if max_cost_usd > 0.0 && cost > max_cost_usd {
+                return Some(format!(
+                    "Cost budget exceeded: spent ${:.4} of ${:.2} allowed for this task.",
+                    cost, max_cost_usd
+                ));
+            }
+        }
+        None
+    }
+}
@@ -1,9 +1,16 @@
+use serde_json::json;
 use tauri::{AppHandle, Emitter, Manager, State};
 use tokio_util::sync::CancellationToken;
 
-use crate::agent::loop_runner;
+use crate::agent::approval::ApprovalDecision;
+use crate::agent::loop_runner::{self, RunStart};
+use crate::agent::pause::PauseToken;
+use crate::agent::queue::QueuedTaskStatus;
+use crate::agent::replay::ReplayOptions;
+use crate::ai::usage::RunUsage;
 use crate::error::{AppError, AppResult};
 use crate::state::{AgentStatus, AppState};
+use crate::storage::runs;
 
 fn restore_main_window(app: &AppHandle) {
     if let Some(cursor_window) = app.get_webview_window("cursor-overlay") {
@@ -16,18 +23,71 @@ pub async fn start_agent(
     task: String,
     app: AppHandle,
     state: State<'_, AppState>,
+) -> AppResult<()> {
+    launch(task.clone(), RunStart::New(task), None, app, &state).await
+}
+
+/// Continue a stored run with its conversation and a fresh screenshot.
+#[tauri::command]
+pub async fn resume_run(id: String, app: AppHandle, state: State<'_, AppState>) -> AppResult<()> {
+    let task = runs::get_run(&runs::runs_dir(&app)?, &id)?.meta.task;
+    launch(task, RunStart::Resume(id), None, app, &state).await
+}
+
+/// Re-execute a stored run's actions, checking screenshots against the recording.
+#[tauri::command]
+pub async fn replay_run(
+    id: String,
+    options: Option<ReplayOptions>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let task = runs::get_run(&runs::runs_dir(&app)?, &id)?.meta.task;
+    let start = RunStart::Replay {
+        run_id: id,
+        options: options.unwrap_or_default(),
+    };
+    launch(task, start, None, app, &state).await
+}
+
+/// Start a run. `queue_id` is the queued task the run executes, whose entry gets the
+/// outcome once the run ends.
+pub(crate) async fn launch(
+    task: String,
+    start: RunStart,
+    queue_id: Option<String>,
+    app: AppHandle,
+    state: &AppState,
 ) -> AppResult<()> {
     let mut agent = state.agent.lock().await;
-    if agent.status == AgentStatus::Running {
-        return Err(AppError::Agent("Agent is already running".into()));
+    if matches!(agent.status, AgentStatus::Running | AgentStatus::Paused) {
+        return Err(AppError::AgentBusy);
+    }
+
+    let settings = state.settings.read().await;
+    if !state.providers.supports(&settings.provider, &settings) {
+        return Err(AppError::Settings(format!(
+            "Unknown provider: {}",
+            settings.provider
+        )));
     }
+    drop(settings);
 
     let cancel_token = CancellationToken::new();
+    let pause_token = PauseToken::new();
     agent.status = AgentStatus::Running;
-    agent.current_task = Some(task.clone());
+    agent.current_task = Some(task);
     agent.iteration = 0;
     agent.estimated_seconds = None;
+    agent.usage = RunUsage::default();
+    agent.limit_reached = None;
+    agent.task_result = None;
+    agent.run_id = None;
+    agent.pending_messages.clear();
     agent.cancel_token = Some(cancel_token.clone());
+    agent.pause_token = Some(pause_token.clone());
+    agent.generation += 1;
+    let generation = agent.generation;
     drop(agent);
 
     let _ = app.emit("agent-status-changed", "running");
@@ -52,36 +112,81 @@ pub async fn start_agent(
 
     // Spawn the agent loop
     let settings = state.settings.clone();
+    let providers = state.providers.clone();
     let agent_state = state.agent.clone();
+    let queue = state.queue.clone();
+    let queue_notify = state.queue_notify.clone();
     let app_handle = app.clone();
 
     tokio::spawn(async move {
         let result = loop_runner::run_agent_loop(
-            task,
-            cancel_token,
+            start,
+            cancel_token.clone(),
+            pause_token,
             settings,
+            providers,
             agent_state.clone(),
             app_handle.clone(),
         )
         .await;
 
+        let cancelled = cancel_token.is_cancelled();
         let mut agent = agent_state.lock().await;
-        match result {
-            Ok(_) => {
-                agent.status = AgentStatus::Idle;
-                let _ = app_handle.emit("agent-status-changed", "idle");
+        // A newer run owns the state (and the overlay) if this one was stopped and
+        // another started before it finished unwinding
+        let current = agent.generation == generation;
+        let (run_id, task_result, limit_message) = if current {
+            match &result {
+                Ok(_) if agent.limit_reached.is_some() => {
+                    agent.status = AgentStatus::LimitReached;
+                    let _ = app_handle.emit("agent-status-changed", "limit_reached");
+                }
+                Ok(_) => {
+                    agent.status = AgentStatus::Idle;
+                    let _ = app_handle.emit("agent-status-changed", "idle");
+                }
+                Err(e) => {
+                    log::error!("Agent loop error: {}", e);
+                    agent.status = AgentStatus::Error;
+                    let _ = app_handle.emit("agent-status-changed", &format!("error:{}", e));
+                }
             }
-            Err(e) => {
-                log::error!("Agent loop error: {}", e);
-                agent.status = AgentStatus::Error;
-                let _ = app_handle.emit("agent-status-changed", &format!("error:{}", e));
+            agent.cancel_token = None;
+            agent.pause_token = None;
+            (
+                agent.run_id.clone(),
+                agent.task_result.clone(),
+                agent.limit_reached.as_ref().map(|hit| hit.message.clone()),
+            )
+        } else {
+            if let Err(e) = &result {
+                log::error!("Agent loop error after stop: {}", e);
             }
-        }
-        agent.cancel_token = None;
+            (None, None, None)
+        };
         drop(agent);
 
         // Restore window to start screen size
-        restore_main_window(&app_handle);
+        if current {
+            restore_main_window(&app_handle);
+        }
+
+        if let Some(id) = queue_id {
+            let (status, error) = match (result, cancelled, limit_message) {
+                (Err(e), _, _) => (QueuedTaskStatus::Failed, Some(e.to_string())),
+                (Ok(_), true, _) => (QueuedTaskStatus::Cancelled, None),
+                (Ok(_), false, Some(message)) => (QueuedTaskStatus::Failed, Some(message)),
+                (Ok(_), false, None) => (QueuedTaskStatus::Finished, None),
+            };
+            let mut queue = queue.lock().await;
+            queue.finish(&id, status, run_id, task_result, error);
+            queue.persist(&app_handle);
+        }
+
+        // A run stopped by the user also holds the queue until it is started again
+        if !cancelled {
+            queue_notify.notify_one();
+        }
     });
 
     Ok(())
@@ -93,6 +198,8 @@ pub async fn stop_agent(app: AppHandle, state: State<'_, AppState>) -> AppResult
     if let Some(token) = agent.cancel_token.take() {
         token.cancel();
     }
+    agent.pause_token = None;
+    agent.pending_approval = None;
     agent.status = AgentStatus::Idle;
     agent.current_task = None;
     agent.iteration = 0;
@@ -105,3 +212,109 @@ pub async fn stop_agent(app: AppHandle, state: State<'_, AppState>) -> AppResult
 
     Ok(())
 }
+
+/// Ask the loop to pause at its next safe point; it reports `paused` once it has.
+#[tauri::command]
+pub async fn pause_agent(state: State<'_, AppState>) -> AppResult<()> {
+    let agent = state.agent.lock().await;
+    match (&agent.status, &agent.pause_token) {
+        (AgentStatus::Running, Some(token)) => {
+            token.pause();
+            Ok(())
+        }
+        _ => Err(AppError::Agent("Agent is not running".into())),
+    }
+}
+
+#[tauri::command]
+pub async fn resume_agent(state: State<'_, AppState>) -> AppResult<()> {
+    let agent = state.agent.lock().await;
+    match &agent.pause_token {
+        Some(token) if token.is_paused() => {
+            token.resume();
+            Ok(())
+        }
+        _ => Err(AppError::Agent("Agent is not paused".into())),
+    }
+}
+
+/// Queue a follow-up message for the running agent; it reaches the model at the next
+/// iteration boundary.
+#[tauri::command]
+pub async fn send_agent_message(
+    text: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err(AppError::Agent("Message is empty".into()));
+    }
+
+    let mut agent = state.agent.lock().await;
+    if !matches!(agent.status, AgentStatus::Running | AgentStatus::Paused) {
+        return Err(AppError::Agent("Agent is not running".into()));
+    }
+    agent.pending_messages.push(text.clone());
+    let iteration = agent.iteration;
+    drop(agent);
+
+    let _ = app.emit(
+        "action-executed",
+        json!({
+            "action_type": "user",
+            "description": text,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "iteration": iteration
+        }),
+    );
+    Ok(())
+}
+
+async fn resolve_approval(state: &AppState, id: &str, decision: ApprovalDecision) -> AppResult<()> {
+    let mut agent = state.agent.lock().await;
+    match agent.pending_approval.take() {
+        Some(pending) if pending.id == id => {
+            pending.resolve(decision);
+            Ok(())
+        }
+        other => {
+            agent.pending_approval = other;
+            Err(AppError::Agent(format!(
+                "No pending approval with id {}",
+                id
+            )))
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn approve_action(id: String, state: State<'_, AppState>) -> AppResult<()> {
+    resolve_approval(
+        &state,
+        &id,
+        ApprovalDecision {
+            approved: true,
+            reason: None,
+        },
+    )
+    .await
+}
+
+/// Reject the pending action; `reason` is passed to the model with the tool result.
+#[tauri::command]
+pub async fn reject_action(
+    id: String,
+    reason: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    resolve_approval(
+        &state,
+        &id,
+        ApprovalDecision {
+            approved: false,
+            reason,
+        },
+    )
+    .await
+}
@@ -0,0 +1,49 @@
+use tauri::{AppHandle, State};
+
+use crate::error::AppResult;
+use crate::memory::store::{MemoryEntry, MemoryInput};
+use crate::state::AppState;
+
+#[tauri::command]
+pub async fn list_memories(state: State<'_, AppState>) -> AppResult<Vec<MemoryEntry>> {
+    Ok(state.memory.lock().await.entries().to_vec())
+}
+
+#[tauri::command]
+pub async fn search_memories(
+    query: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<MemoryEntry>> {
+    let store = state.memory.lock().await;
+    Ok(store
+        .search(&query, limit.unwrap_or(usize::MAX))
+        .into_iter()
+        .cloned()
+        .collect())
+}
+
+/// Create a memory, or edit the one named by `memory.id`.
+#[tauri::command]
+pub async fn save_memory(
+    memory: MemoryInput,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<MemoryEntry> {
+    let mut store = state.memory.lock().await;
+    let entry = store.write(memory, None)?;
+    store.persist(&app);
+    Ok(entry)
+}
+
+#[tauri::command]
+pub async fn delete_memory(
+    id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let mut store = state.memory.lock().await;
+    store.delete(&id)?;
+    store.persist(&app);
+    Ok(())
+}
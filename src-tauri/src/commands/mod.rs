@@ -1,5 +1,9 @@
 pub mod agent;
 pub mod input;
+pub mod memory;
+pub mod queue;
+pub mod runs;
+pub mod schedules;
 pub mod screenshot;
 pub mod settings;
 pub mod shell;
@@ -0,0 +1,130 @@
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::agent::loop_runner::RunStart;
+use crate::agent::queue::{QueuedTask, QueuedTaskStatus};
+use crate::commands::agent::launch;
+use crate::error::{AppError, AppResult};
+use crate::state::{AgentStatus, AppState};
+
+/// Start the background task that runs queued tasks one at a time. It wakes on
+/// `AppState::queue_notify` and starts the next task once the agent is free.
+pub fn spawn_queue_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        loop {
+            state.queue_notify.notified().await;
+            start_next(&app, &state).await;
+        }
+    });
+}
+
+async fn start_next(app: &AppHandle, state: &AppState) {
+    if matches!(
+        state.agent.lock().await.status,
+        AgentStatus::Running | AgentStatus::Paused
+    ) {
+        return;
+    }
+
+    let mut queue = state.queue.lock().await;
+    let Some(next) = queue.start_next() else {
+        return;
+    };
+    queue.persist(app);
+    drop(queue);
+
+    log::info!("Starting queued task {}", next.id);
+    let _ = app.emit(
+        "queue-task-started",
+        json!({ "id": next.id, "task": next.task }),
+    );
+    let result = launch(
+        next.task.clone(),
+        RunStart::New(next.task),
+        Some(next.id.clone()),
+        app.clone(),
+        state,
+    )
+    .await;
+
+    // Another run started in between; this task waits for that one to end
+    if let Err(AppError::AgentBusy) = result {
+        log::info!("Agent became busy, returning task {} to the queue", next.id);
+        let mut queue = state.queue.lock().await;
+        queue.requeue(&next.id);
+        queue.persist(app);
+    } else if let Err(e) = result {
+        log::error!("Failed to start queued task {}: {}", next.id, e);
+        let mut queue = state.queue.lock().await;
+        queue.finish(
+            &next.id,
+            QueuedTaskStatus::Failed,
+            None,
+            None,
+            Some(e.to_string()),
+        );
+        queue.persist(app);
+        drop(queue);
+        state.queue_notify.notify_one();
+    }
+}
+
+/// Add a task to the end of the queue. It starts right away if the agent is idle.
+#[tauri::command]
+pub async fn enqueue_task(
+    task: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<QueuedTask> {
+    let task = task.trim().to_string();
+    if task.is_empty() {
+        return Err(AppError::Agent("Task is empty".into()));
+    }
+
+    let mut queue = state.queue.lock().await;
+    let queued = queue.enqueue(task);
+    queue.persist(&app);
+    drop(queue);
+
+    state.queue_notify.notify_one();
+    Ok(queued)
+}
+
+#[tauri::command]
+pub async fn list_queue(state: State<'_, AppState>) -> AppResult<Vec<QueuedTask>> {
+    Ok(state.queue.lock().await.tasks().to_vec())
+}
+
+/// Reorder the waiting tasks; `ids` lists every queued task in its new order.
+#[tauri::command]
+pub async fn reorder_queue(
+    ids: Vec<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<QueuedTask>> {
+    let mut queue = state.queue.lock().await;
+    queue.reorder(&ids)?;
+    queue.persist(&app);
+    Ok(queue.tasks().to_vec())
+}
+
+#[tauri::command]
+pub async fn cancel_queued_task(
+    id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let mut queue = state.queue.lock().await;
+    queue.cancel(&id)?;
+    queue.persist(&app);
+    Ok(())
+}
+
+/// Start working through the queue again, e.g. after the app restarted or the user
+/// stopped a queued run.
+#[tauri::command]
+pub async fn start_queue(state: State<'_, AppState>) -> AppResult<()> {
+    state.queue_notify.notify_one();
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use tauri::{AppHandle, State};
+
+use crate::error::{AppError, AppResult};
+use crate::state::AppState;
+use crate::storage::runs::{self, RunMeta, RunRecord};
+
+#[tauri::command]
+pub async fn list_runs(app: AppHandle) -> AppResult<Vec<RunMeta>> {
+    runs::list_runs(&runs::runs_dir(&app)?)
+}
+
+#[tauri::command]
+pub async fn get_run(id: String, app: AppHandle) -> AppResult<RunRecord> {
+    runs::get_run(&runs::runs_dir(&app)?, &id)
+}
+
+#[tauri::command]
+pub async fn delete_run(id: String, app: AppHandle, state: State<'_, AppState>) -> AppResult<()> {
+    let agent = state.agent.lock().await;
+    if agent.cancel_token.is_some() && agent.run_id.as_deref() == Some(id.as_str()) {
+        return Err(AppError::Agent("Cannot delete the run in progress".into()));
+    }
+    drop(agent);
+    runs::delete_run(&runs::runs_dir(&app)?, &id)
+}
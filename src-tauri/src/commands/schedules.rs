@@ -0,0 +1,71 @@
+use tauri::{AppHandle, State};
+
+use crate::error::AppResult;
+use crate::scheduler::schedule::{NewSchedule, Schedule};
+use crate::state::AppState;
+
+#[tauri::command]
+pub async fn create_schedule(
+    schedule: NewSchedule,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<Schedule> {
+    let mut schedules = state.schedules.lock().await;
+    let created = schedules.create(schedule)?;
+    schedules.persist(&app);
+    drop(schedules);
+
+    state.schedule_notify.notify_one();
+    Ok(created)
+}
+
+#[tauri::command]
+pub async fn list_schedules(state: State<'_, AppState>) -> AppResult<Vec<Schedule>> {
+    Ok(state.schedules.lock().await.items().to_vec())
+}
+
+#[tauri::command]
+pub async fn pause_schedule(
+    id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<Schedule> {
+    set_paused(&id, true, &app, &state).await
+}
+
+/// Resume a paused schedule from its next regular time.
+#[tauri::command]
+pub async fn resume_schedule(
+    id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<Schedule> {
+    set_paused(&id, false, &app, &state).await
+}
+
+async fn set_paused(
+    id: &str,
+    paused: bool,
+    app: &AppHandle,
+    state: &AppState,
+) -> AppResult<Schedule> {
+    let mut schedules = state.schedules.lock().await;
+    let schedule = schedules.set_paused(id, paused)?;
+    schedules.persist(app);
+    drop(schedules);
+
+    state.schedule_notify.notify_one();
+    Ok(schedule)
+}
+
+#[tauri::command]
+pub async fn delete_schedule(
+    id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let mut schedules = state.schedules.lock().await;
+    schedules.delete(&id)?;
+    schedules.persist(&app);
+    Ok(())
+}
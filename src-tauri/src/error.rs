@@ -15,12 +15,26 @@ pub enum AppError {
     #[error("AI provider error: {0}")]
     AiProvider(String),
 
+    #[error("{provider} API error {status}: {body}")]
+    ProviderHttp {
+        provider: String,
+        status: reqwest::StatusCode,
+        body: String,
+        retry_after: Option<std::time::Duration>,
+    },
+
     #[error("Agent error: {0}")]
     Agent(String),
 
+    #[error("Agent is already running")]
+    AgentBusy,
+
     #[error("Window error: {0}")]
     Window(String),
 
+    #[error("Memory error: {0}")]
+    Memory(String),
+
     #[error("Settings error: {0}")]
     Settings(String),
 
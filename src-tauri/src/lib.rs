@@ -3,13 +3,19 @@ mod ai;
 mod commands;
 mod error;
 mod input;
+mod memory;
 mod platform;
+mod scheduler;
 mod screen;
 mod shell;
 mod state;
+mod storage;
 
-use state::AppState;
-use tauri::{RunEvent, WindowEvent};
+use agent::queue::TaskQueue;
+use memory::store::MemoryStore;
+use scheduler::schedule::Schedules;
+use state::{AppState, Settings};
+use tauri::{Manager, RunEvent, WindowEvent};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -25,6 +31,52 @@ pub fn run() {
                 )?;
             }
 
+            // Saved settings, so runs started before the frontend syncs its copy (a
+            // schedule due at startup) use the right provider, keys and limits
+            match Settings::load(app.handle()) {
+                Ok(Some(settings)) => {
+                    *app.state::<AppState>().settings.blocking_write() = settings;
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to load settings: {}", e),
+            }
+
+            // Runs still marked as running were cut short by a crash or a forced quit
+            match storage::runs::runs_dir(app.handle())
+                .and_then(|dir| storage::runs::mark_interrupted_runs(&dir))
+            {
+                Ok(interrupted) if !interrupted.is_empty() => {
+                    log::warn!("Found {} interrupted run(s)", interrupted.len());
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("Failed to check for interrupted runs: {}", e),
+            }
+
+            // Restore the task queue and pick up where it left off with the tasks
+            // still waiting
+            match TaskQueue::load(app.handle()) {
+                Ok(queue) => {
+                    queue.persist(app.handle());
+                    *app.state::<AppState>().queue.blocking_lock() = queue;
+                }
+                Err(e) => log::warn!("Failed to load task queue: {}", e),
+            }
+            commands::queue::spawn_queue_worker(app.handle().clone());
+            app.state::<AppState>().queue_notify.notify_one();
+
+            // Schedules that came due while the app was closed are handled by their
+            // missed-run policy on the timer's first pass
+            match Schedules::load(app.handle()) {
+                Ok(schedules) => *app.state::<AppState>().schedules.blocking_lock() = schedules,
+                Err(e) => log::warn!("Failed to load schedules: {}", e),
+            }
+            scheduler::runner::spawn_scheduler(app.handle().clone());
+
+            match MemoryStore::load(app.handle()) {
+                Ok(memory) => *app.state::<AppState>().memory.blocking_lock() = memory,
+                Err(e) => log::warn!("Failed to load memory: {}", e),
+            }
+
             // Create cursor overlay window (hidden by default, fullscreen, click-through)
             let cursor_window = tauri::WebviewWindowBuilder::new(
                 app,
@@ -86,9 +138,35 @@ pub fn run() {
             commands::window::focus_window,
             commands::agent::start_agent,
             commands::agent::stop_agent,
+            commands::agent::pause_agent,
+            commands::agent::resume_agent,
+            commands::agent::send_agent_message,
+            commands::agent::approve_action,
+            commands::agent::reject_action,
+            commands::agent::resume_run,
+            commands::agent::replay_run,
+            commands::queue::enqueue_task,
+            commands::queue::list_queue,
+            commands::queue::reorder_queue,
+            commands::queue::cancel_queued_task,
+            commands::queue::start_queue,
+            commands::schedules::create_schedule,
+            commands::schedules::list_schedules,
+            commands::schedules::pause_schedule,
+            commands::schedules::resume_schedule,
+            commands::schedules::delete_schedule,
+            commands::memory::list_memories,
+            commands::memory::search_memories,
+            commands::memory::save_memory,
+            commands::memory::delete_memory,
+            commands::runs::list_runs,
+            commands::runs::get_run,
+            commands::runs::delete_run,
             commands::settings::get_settings,
             commands::settings::save_settings,
             commands::settings::list_ollama_models,
+            commands::settings::list_profile_models,
+            commands::settings::preview_system_prompt,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
@@ -0,0 +1,186 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::{AppError, AppResult};
+use crate::storage::json_file;
+
+const MEMORY_FILE: &str = "memory.json";
+
+/// Longest memory content accepted, so a single entry can't flood the task message.
+const MAX_CONTENT_CHARS: usize = 2_000;
+
+/// Words too common to say anything about relevance.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "into", "is", "it", "me",
+    "my", "of", "on", "or", "our", "please", "the", "then", "this", "to", "up", "use", "we",
+    "with", "you", "your",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Run that last wrote the entry; `None` when the user wrote it.
+    pub run_id: Option<String>,
+}
+
+/// Input for creating or updating an entry. Without an `id` a new entry is created.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MemoryInput {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub title: String,
+    pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Facts the agent keeps across tasks, in `memory.json` in the app data directory.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    entries: Vec<MemoryEntry>,
+}
+
+/// Lowercase search terms of a text, without stopwords and one-letter words.
+fn terms(text: &str) -> Vec<String> {
+    let mut terms: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+impl MemoryEntry {
+    /// Keyword relevance: a term in the title or tags counts more than one that only
+    /// appears in the content.
+    fn score(&self, query: &[String]) -> u32 {
+        let title = terms(&self.title);
+        let tags = terms(&self.tags.join(" "));
+        let content = terms(&self.content);
+        query
+            .iter()
+            .map(|term| {
+                if title.contains(term) || tags.contains(term) {
+                    3
+                } else if content.contains(term) {
+                    1
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+}
+
+impl MemoryStore {
+    pub fn load(app: &AppHandle) -> AppResult<Self> {
+        Ok(Self {
+            entries: json_file::load(app, MEMORY_FILE)?,
+        })
+    }
+
+    /// Save the entries and tell the frontend they changed.
+    pub fn persist(&self, app: &AppHandle) {
+        json_file::persist(
+            app,
+            MEMORY_FILE,
+            "memories-updated",
+            "memory",
+            &self.entries,
+        );
+    }
+
+    pub fn entries(&self) -> &[MemoryEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, id: &str) -> AppResult<&MemoryEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.id == id)
+            .ok_or_else(|| AppError::Memory(format!("No memory with id {}", id)))
+    }
+
+    /// Create an entry, or update the one named by `input.id`.
+    pub fn write(&mut self, input: MemoryInput, run_id: Option<String>) -> AppResult<MemoryEntry> {
+        let title = input.title.trim().to_string();
+        let content = input.content.trim().to_string();
+        if title.is_empty() || content.is_empty() {
+            return Err(AppError::Memory(
+                "A memory needs a title and content".into(),
+            ));
+        }
+        if content.chars().count() > MAX_CONTENT_CHARS {
+            return Err(AppError::Memory(format!(
+                "Memory content is limited to {} characters",
+                MAX_CONTENT_CHARS
+            )));
+        }
+        let tags: Vec<String> = input
+            .tags
+            .iter()
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        let now = Utc::now();
+        if let Some(id) = input.id {
+            let entry = self
+                .entries
+                .iter_mut()
+                .find(|e| e.id == id)
+                .ok_or_else(|| AppError::Memory(format!("No memory with id {}", id)))?;
+            entry.title = title;
+            entry.content = content;
+            entry.tags = tags;
+            entry.updated_at = now;
+            entry.run_id = run_id;
+            return Ok(entry.clone());
+        }
+
+        let entry = MemoryEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            title,
+            content,
+            tags,
+            created_at: now,
+            updated_at: now,
+            run_id,
+        };
+        self.entries.push(entry.clone());
+        Ok(entry)
+    }
+
+    pub fn delete(&mut self, id: &str) -> AppResult<()> {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.id != id);
+        if self.entries.len() == before {
+            return Err(AppError::Memory(format!("No memory with id {}", id)));
+        }
+        Ok(())
+    }
+
+    /// Entries matching any keyword of `query`, most relevant (then most recently
+    /// updated) first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&MemoryEntry> {
+        let query = terms(query);
+        let mut scored: Vec<(u32, &MemoryEntry)> = self
+            .entries
+            .iter()
+            .map(|entry| (entry.score(&query), entry))
+            .filter(|(score, _)| *score > 0)
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.updated_at.cmp(&a.1.updated_at)));
+        scored.into_iter().take(limit).map(|(_, e)| e).collect()
+    }
+}
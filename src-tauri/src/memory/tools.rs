@@ -0,0 +1,107 @@
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::error::{AppError, AppResult};
+use crate::memory::store::{MemoryEntry, MemoryInput, MemoryStore};
+
+/// Results returned by `memory_search` when the model doesn't ask for a number.
+const DEFAULT_SEARCH_LIMIT: usize = 5;
+
+/// Length of the content preview in search results.
+const SNIPPET_CHARS: usize = 160;
+
+pub fn is_memory_tool(name: &str) -> bool {
+    matches!(name, "memory_read" | "memory_write" | "memory_search")
+}
+
+fn snippet(entry: &MemoryEntry) -> String {
+    match entry.content.char_indices().nth(SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}…", &entry.content[..end]),
+        None => entry.content.clone(),
+    }
+}
+
+fn describe(entry: &MemoryEntry) -> String {
+    if entry.tags.is_empty() {
+        format!("[{}] {}", entry.id, entry.title)
+    } else {
+        format!("[{}] {} ({})", entry.id, entry.title, entry.tags.join(", "))
+    }
+}
+
+/// One-line description of a memory tool call for the action log.
+pub fn describe_memory_call(name: &str, input: &Value) -> String {
+    match name {
+        "memory_search" => format!(
+            "Search memory for \"{}\"",
+            input["query"].as_str().unwrap_or_default()
+        ),
+        "memory_read" => format!("Read memory {}", input["id"].as_str().unwrap_or_default()),
+        _ => format!("Remember: {}", input["title"].as_str().unwrap_or_default()),
+    }
+}
+
+/// Run one of the memory tools and return the text of its tool result. Writes are
+/// saved right away so they survive a crash later in the run.
+pub fn run_memory_tool(
+    name: &str,
+    input: &Value,
+    store: &mut MemoryStore,
+    run_id: &str,
+    app: &AppHandle,
+) -> AppResult<String> {
+    match name {
+        "memory_search" => {
+            let query = input["query"].as_str().unwrap_or_default();
+            let limit = input["limit"]
+                .as_u64()
+                .map_or(DEFAULT_SEARCH_LIMIT, |n| n as usize);
+            let found = store.search(query, limit);
+            if found.is_empty() {
+                return Ok(format!("No memories match \"{}\".", query));
+            }
+            Ok(found
+                .iter()
+                .map(|entry| format!("{}: {}", describe(entry), snippet(entry)))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        "memory_read" => {
+            let id = input["id"]
+                .as_str()
+                .ok_or_else(|| AppError::Memory("memory_read requires an id".into()))?;
+            let entry = store.get(id)?;
+            Ok(format!("{}\n\n{}", describe(entry), entry.content))
+        }
+        "memory_write" => {
+            let input: MemoryInput = serde_json::from_value(input.clone())
+                .map_err(|e| AppError::Memory(format!("Invalid memory_write input: {}", e)))?;
+            let updating = input.id.is_some();
+            let entry = store.write(input, Some(run_id.to_string()))?;
+            store.persist(app);
+            Ok(format!(
+                "{} memory [{}].",
+                if updating { "Updated" } else { "Saved" },
+                entry.id
+            ))
+        }
+        _ => Err(AppError::Memory(format!("Unknown memory tool {}", name))),
+    }
+}
+
+/// Note listing the memories most relevant to a task, for the initial task message.
+/// `None` when nothing matches.
+pub fn relevant_memories_note(store: &MemoryStore, task: &str, count: usize) -> Option<String> {
+    let found = store.search(task, count);
+    if found.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = found
+        .iter()
+        .map(|entry| format!("- {}: {}", describe(entry), entry.content))
+        .collect();
+    Some(format!(
+        "Memories from earlier tasks that may help (use memory_write with the id to correct one that is out of date):\n{}",
+        lines.join("\n")
+    ))
+}
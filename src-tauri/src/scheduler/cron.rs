@@ -0,0 +1,156 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+use crate::error::{AppError, AppResult};
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Upper bound on the steps taken looking for the next match, so expressions that
+/// can never fire (e.g. February 30th) end the search instead of looping.
+const MAX_STEPS: u32 = 100_000;
+
+/// A standard five-field cron expression (`minute hour day-of-month month
+/// day-of-week`) in local time. Fields accept `*`, numbers, ranges, lists and steps
+/// (`*/15`, `1-5`, `9,17`, `0-30/10`); months and weekdays also accept three-letter
+/// names. As in cron, when both day fields are restricted a day matching either
+/// one matches.
+#[derive(Debug, Clone)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl CronExpr {
+    pub fn parse(expression: &str) -> AppResult<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(AppError::Settings(format!(
+                "Cron expression needs 5 fields (minute hour day month weekday), got \"{}\"",
+                expression
+            )));
+        };
+
+        let mut days_of_week = parse_field(day_of_week, 0, 7, &DAY_NAMES, 0)?;
+        // 7 is Sunday too
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[], 0)?,
+            hours: parse_field(hour, 0, 23, &[], 0)?,
+            days_of_month: parse_field(day_of_month, 1, 31, &[], 1)?,
+            months: parse_field(month, 1, 12, &MONTH_NAMES, 1)?,
+            days_of_week,
+            any_day_of_month: day_of_month == "*",
+            any_day_of_week: day_of_week == "*",
+        })
+    }
+
+    /// The first matching minute strictly after `after`, or `None` if the expression
+    /// never matches.
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)?;
+        let mut t = start + Duration::minutes(1);
+
+        for _ in 0..MAX_STEPS {
+            if !has(self.months, t.month()) {
+                let (year, month) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !self.day_matches(&t) {
+                t = (t.date() + Duration::days(1)).and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !has(self.hours, t.hour()) {
+                t = t.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+            if !has(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+                continue;
+            }
+            // Times skipped by a DST change don't exist locally
+            if let Some(local) = Local.from_local_datetime(&t).earliest() {
+                return Some(local);
+            }
+            t += Duration::minutes(1);
+        }
+        None
+    }
+
+    fn day_matches(&self, t: &NaiveDateTime) -> bool {
+        let day_of_month = has(self.days_of_month, t.day());
+        let day_of_week = has(self.days_of_week, t.weekday().num_days_from_sunday());
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+fn has(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Parse one field into a bit mask of the values it allows. `names` map to values
+/// starting at `name_base`.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str], name_base: u32) -> AppResult<u64> {
+    let invalid = || AppError::Settings(format!("Invalid cron field \"{}\"", field));
+    let value = |s: &str| -> AppResult<u32> {
+        let lower = s.to_ascii_lowercase();
+        let v = match names.iter().position(|n| *n == lower) {
+            Some(i) => i as u32 + name_base,
+            None => s.parse().map_err(|_| invalid())?,
+        };
+        if v < min || v > max {
+            return Err(AppError::Settings(format!(
+                "Cron value {} out of range {}-{} in \"{}\"",
+                v, min, max, field
+            )));
+        }
+        Ok(v)
+    };
+
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (value(a)?, value(b)?),
+                // `5/10` means from 5 to the end in steps of 10
+                None if part.contains('/') => (value(range)?, max),
+                None => {
+                    let v = value(range)?;
+                    (v, v)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for v in (start..=end).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
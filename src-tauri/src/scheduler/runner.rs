@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::agent::loop_runner::RunStart;
+use crate::commands::agent::launch;
+use crate::scheduler::schedule::{DueRun, WhenBusy};
+use crate::state::{AgentStatus, AppState};
+
+/// Longest the timer sleeps before checking the wall clock again. Monotonic sleeps
+/// can stall while the machine is suspended, so waking up regularly is what notices
+/// runs that came due during sleep.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// Start the background timer that fires due schedules. It also wakes on
+/// `AppState::schedule_notify` whenever schedules change.
+pub fn spawn_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        loop {
+            let now = Utc::now();
+            let mut schedules = state.schedules.lock().await;
+            if schedules.next_wake().is_some_and(|at| at <= now) {
+                let due = schedules.take_due(now);
+                drop(schedules);
+                for run in due {
+                    let outcome = fire(&app, &state, &run).await;
+                    state
+                        .schedules
+                        .lock()
+                        .await
+                        .record_outcome(&run.id, outcome);
+                }
+                state.schedules.lock().await.persist(&app);
+            } else {
+                drop(schedules);
+            }
+
+            let wait = state
+                .schedules
+                .lock()
+                .await
+                .next_wake()
+                .map_or(MAX_SLEEP, |at| {
+                    // Already due (negative durations don't convert) means no wait
+                    (at - Utc::now())
+                        .to_std()
+                        .unwrap_or_default()
+                        .min(MAX_SLEEP)
+                });
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = state.schedule_notify.notified() => {}
+            }
+        }
+    });
+}
+
+/// Start a due schedule's task, or queue or skip it if the agent is busy. Returns
+/// the outcome recorded on the schedule.
+async fn fire(app: &AppHandle, state: &AppState, run: &DueRun) -> String {
+    let busy = matches!(
+        state.agent.lock().await.status,
+        AgentStatus::Running | AgentStatus::Paused
+    );
+    let outcome = match (busy, run.when_busy) {
+        (true, WhenBusy::Skip) => {
+            log::info!("Schedule {} skipped: agent is busy", run.name);
+            "skipped".to_string()
+        }
+        (true, WhenBusy::Queue) => {
+            let mut queue = state.queue.lock().await;
+            queue.enqueue(run.task.clone());
+            queue.persist(app);
+            drop(queue);
+            state.queue_notify.notify_one();
+            log::info!("Schedule {} queued behind the current run", run.name);
+            "queued".to_string()
+        }
+        (false, _) => {
+            let result = launch(
+                run.task.clone(),
+                RunStart::New(run.task.clone()),
+                None,
+                app.clone(),
+                state,
+            )
+            .await;
+            match result {
+                Ok(()) => {
+                    log::info!("Schedule {} started", run.name);
+                    "started".to_string()
+                }
+                Err(e) => {
+                    log::error!("Schedule {} failed to start: {}", run.name, e);
+                    format!("failed: {}", e)
+                }
+            }
+        }
+    };
+
+    let _ = app.emit(
+        "schedule-fired",
+        json!({ "id": run.id, "name": run.name, "task": run.task, "outcome": outcome }),
+    );
+    outcome
+}
@@ -0,0 +1,252 @@
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::{AppError, AppResult};
+use crate::scheduler::cron::CronExpr;
+use crate::storage::json_file;
+
+const SCHEDULES_FILE: &str = "schedules.json";
+
+/// How late a run may start and still count as on time. Anything later was missed,
+/// e.g. because the machine was asleep or the app was closed.
+const MISSED_GRACE_SECS: i64 = 120;
+
+/// When a schedule fires.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleTiming {
+    /// Five-field cron expression in local time.
+    Cron { expression: String },
+    /// Fire once at a fixed time.
+    Once { at: DateTime<Utc> },
+}
+
+/// What to do when a schedule fires while the agent is busy.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WhenBusy {
+    Skip,
+    /// Add the task to the task queue.
+    #[default]
+    Queue,
+}
+
+/// What to do with runs that came due while the app was closed or the machine
+/// was asleep.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    /// Drop them and wait for the next regular time.
+    #[default]
+    Skip,
+    /// Run once as soon as possible, however many times were missed.
+    RunOnce,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    pub name: String,
+    pub task: String,
+    pub timing: ScheduleTiming,
+    #[serde(default)]
+    pub when_busy: WhenBusy,
+    #[serde(default)]
+    pub missed_run: MissedRunPolicy,
+    #[serde(default)]
+    pub paused: bool,
+    pub created_at: DateTime<Utc>,
+    /// `None` once a one-shot schedule has fired or a cron expression can't match.
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_run: Option<DateTime<Utc>>,
+    /// What happened the last time the schedule came due: `started`, `queued`,
+    /// `skipped` (agent busy), `missed` or `failed: <error>`.
+    pub last_outcome: Option<String>,
+}
+
+/// Input for `create_schedule`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewSchedule {
+    #[serde(default)]
+    pub name: String,
+    pub task: String,
+    pub timing: ScheduleTiming,
+    #[serde(default)]
+    pub when_busy: WhenBusy,
+    #[serde(default)]
+    pub missed_run: MissedRunPolicy,
+}
+
+/// A schedule that came due, to be started by the runner.
+pub struct DueRun {
+    pub id: String,
+    pub name: String,
+    pub task: String,
+    pub when_busy: WhenBusy,
+}
+
+impl ScheduleTiming {
+    fn validate(&self) -> AppResult<()> {
+        match self {
+            Self::Cron { expression } => {
+                CronExpr::parse(expression)?
+                    .next_after(Local::now())
+                    .ok_or_else(|| {
+                        AppError::Settings(format!(
+                            "Cron expression \"{}\" never matches",
+                            expression
+                        ))
+                    })?;
+            }
+            Self::Once { at } if *at <= Utc::now() => {
+                return Err(AppError::Settings("Scheduled time is in the past".into()));
+            }
+            Self::Once { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// The first time this timing fires after `after`.
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Cron { expression } => CronExpr::parse(expression)
+                .ok()?
+                .next_after(after.with_timezone(&Local))
+                .map(|t| t.with_timezone(&Utc)),
+            Self::Once { at } => (*at > after).then_some(*at),
+        }
+    }
+}
+
+/// All schedules, kept in `schedules.json` in the app data directory.
+#[derive(Debug, Default)]
+pub struct Schedules {
+    items: Vec<Schedule>,
+}
+
+impl Schedules {
+    pub fn load(app: &AppHandle) -> AppResult<Self> {
+        Ok(Self {
+            items: json_file::load(app, SCHEDULES_FILE)?,
+        })
+    }
+
+    /// Save the schedules and tell the frontend they changed.
+    pub fn persist(&self, app: &AppHandle) {
+        json_file::persist(
+            app,
+            SCHEDULES_FILE,
+            "schedules-updated",
+            "schedules",
+            &self.items,
+        );
+    }
+
+    pub fn items(&self) -> &[Schedule] {
+        &self.items
+    }
+
+    pub fn create(&mut self, new: NewSchedule) -> AppResult<Schedule> {
+        let task = new.task.trim().to_string();
+        if task.is_empty() {
+            return Err(AppError::Settings("Scheduled task is empty".into()));
+        }
+        new.timing.validate()?;
+
+        let now = Utc::now();
+        let name = match new.name.trim() {
+            "" => task.chars().take(40).collect(),
+            name => name.to_string(),
+        };
+        let schedule = Schedule {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            task,
+            next_run: new.timing.next_after(now),
+            timing: new.timing,
+            when_busy: new.when_busy,
+            missed_run: new.missed_run,
+            paused: false,
+            created_at: now,
+            last_run: None,
+            last_outcome: None,
+        };
+        self.items.push(schedule.clone());
+        Ok(schedule)
+    }
+
+    /// Pause or resume a schedule. Resuming starts counting from now, so runs that
+    /// fell inside the pause are not treated as missed.
+    pub fn set_paused(&mut self, id: &str, paused: bool) -> AppResult<Schedule> {
+        let schedule = self.get_mut(id)?;
+        schedule.paused = paused;
+        if !paused {
+            schedule.next_run = schedule.timing.next_after(Utc::now());
+        }
+        Ok(schedule.clone())
+    }
+
+    pub fn delete(&mut self, id: &str) -> AppResult<()> {
+        let before = self.items.len();
+        self.items.retain(|s| s.id != id);
+        if self.items.len() == before {
+            return Err(AppError::Settings(format!("No schedule with id {}", id)));
+        }
+        Ok(())
+    }
+
+    pub fn record_outcome(&mut self, id: &str, outcome: String) {
+        if let Ok(schedule) = self.get_mut(id) {
+            schedule.last_outcome = Some(outcome);
+        }
+    }
+
+    /// The earliest upcoming run of an active schedule.
+    pub fn next_wake(&self) -> Option<DateTime<Utc>> {
+        self.items
+            .iter()
+            .filter(|s| !s.paused)
+            .filter_map(|s| s.next_run)
+            .min()
+    }
+
+    /// Advance every active schedule whose time has come and return the ones that
+    /// should run now. Runs missed by more than the grace period only fire under
+    /// `MissedRunPolicy::RunOnce`, and only once however many were missed.
+    pub fn take_due(&mut self, now: DateTime<Utc>) -> Vec<DueRun> {
+        let mut due = Vec::new();
+        for schedule in self.items.iter_mut().filter(|s| !s.paused) {
+            let Some(next_run) = schedule.next_run else {
+                continue;
+            };
+            if next_run > now {
+                continue;
+            }
+
+            schedule.next_run = schedule.timing.next_after(now);
+            let missed = (now - next_run).num_seconds() > MISSED_GRACE_SECS;
+            if missed && schedule.missed_run == MissedRunPolicy::Skip {
+                log::info!("Skipping missed run of schedule {}", schedule.name);
+                schedule.last_outcome = Some("missed".into());
+                continue;
+            }
+
+            schedule.last_run = Some(now);
+            due.push(DueRun {
+                id: schedule.id.clone(),
+                name: schedule.name.clone(),
+                task: schedule.task.clone(),
+                when_busy: schedule.when_busy,
+            });
+        }
+        due
+    }
+
+    fn get_mut(&mut self, id: &str) -> AppResult<&mut Schedule> {
+        self.items
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| AppError::Settings(format!("No schedule with id {}", id)))
+    }
+}
@@ -10,6 +10,15 @@ pub struct ShellResult {
     pub exit_code: i32,
 }
 
+/// Shell `run_command` runs commands in, as named in the system prompt.
+pub fn shell_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "PowerShell"
+    } else {
+        "bash"
+    }
+}
+
 pub async fn run_command(cmd: &str, timeout_secs: u64) -> AppResult<ShellResult> {
     let child = if cfg!(target_os = "windows") {
         Command::new("powershell")
@@ -1,8 +1,30 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use tokio::sync::{Mutex, Notify, RwLock};
 use tokio_util::sync::CancellationToken;
 
+use crate::agent::approval::{default_approval_policies, ApprovalPolicy, PendingApproval};
+use crate::agent::completion::TaskResult;
+use crate::agent::limits::LimitHit;
+use crate::agent::pause::PauseToken;
+use crate::agent::prompt::PromptOverride;
+use crate::agent::queue::TaskQueue;
+use crate::ai::gemini::DEFAULT_GEMINI_BASE_URL;
+use crate::ai::ollama::OllamaOptions;
+use crate::ai::openai_compat::ProviderProfile;
+use crate::ai::provider::ProviderRegistry;
+use crate::ai::usage::{ModelPrice, RunUsage};
+use crate::error::{AppError, AppResult};
+use crate::memory::store::MemoryStore;
+use crate::scheduler::schedule::Schedules;
+
+/// Store file and key the frontend keeps its settings under.
+const SETTINGS_STORE_FILE: &str = "settings.json";
+const SETTINGS_STORE_KEY: &str = "settings";
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum AgentStatus {
@@ -10,20 +32,90 @@ pub enum AgentStatus {
     Running,
     Paused,
     Error,
+    /// Stopped by one of the run limits in `Settings`.
+    #[serde(rename = "limit_reached")]
+    LimitReached,
+}
+
+/// A provider/model pair to fall back to when the current one keeps failing.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FallbackProvider {
+    pub provider: String,
+    pub model: String,
 }
 
+/// Fields missing from settings saved by an older version keep their defaults.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     pub anthropic_api_key: String,
     pub openai_api_key: String,
     pub openrouter_api_key: String,
     pub ollama_api_key: String,
     pub ollama_base_url: String,
+    pub ollama_options: OllamaOptions,
+    pub gemini_api_key: String,
+    /// Overridable so the client can be pointed at a proxy or a local mock server.
+    pub gemini_base_url: String,
+    /// User-defined OpenAI-compatible endpoints, selectable by their `id`.
+    pub custom_providers: Vec<ProviderProfile>,
     pub provider: String,
     pub model: String,
+    /// Tried in order once retries against the current provider are exhausted.
+    pub fallback_providers: Vec<FallbackProvider>,
     pub display_width: u32,
     pub display_height: u32,
     pub shell_timeout_secs: u64,
+    /// Extended thinking budget in tokens (Anthropic, Gemini); 0 disables it.
+    pub thinking_budget_tokens: u32,
+    /// OpenAI o-series `reasoning_effort` (`low`/`medium`/`high`); empty for the default.
+    pub reasoning_effort: String,
+    pub retry_max_attempts: u32,
+    pub retry_max_delay_secs: u64,
+    /// USD per million tokens, keyed by model id (or a prefix of it).
+    pub model_prices: HashMap<String, ModelPrice>,
+    /// Per-task limits; 0 disables the limit.
+    pub max_tokens_per_task: u64,
+    pub max_cost_per_task_usd: f64,
+    /// Run limits; 0 disables the limit.
+    pub max_iterations: u32,
+    pub max_run_duration_secs: u64,
+    /// Per-run caps keyed by action kind, e.g. `bash_command` or `left_click`.
+    pub max_actions: HashMap<String, u32>,
+    /// Approval policy keyed by action kind; kinds not listed run without asking.
+    pub approval_policies: HashMap<String, ApprovalPolicy>,
+    /// How long an `ask` action waits for the user before it is rejected.
+    pub approval_timeout_secs: u64,
+    /// Stuck-loop detection: identical actions in a row, and actions without any
+    /// change on screen, before the model is nudged (0 disables the check).
+    pub stuck_repeat_threshold: u32,
+    pub stuck_unchanged_threshold: u32,
+    /// Nudges allowed before a stuck run is paused for the user.
+    pub stuck_max_nudges: u32,
+    /// Reminders to call `task_complete` when the model stops without it; once used
+    /// up, a reply without tool calls ends the run with an unknown status.
+    pub completion_reminders: u32,
+    /// Estimated conversation size in tokens above which older turns are condensed
+    /// into a model-written progress note (0 disables compaction).
+    pub compaction_threshold_tokens: u64,
+    /// Most recent messages compaction always keeps verbatim.
+    pub compaction_keep_recent: u32,
+    /// Screenshots kept in the history at full resolution, then as grayscale
+    /// thumbnails of the given width; older ones are replaced by a text note.
+    pub screenshot_history_full: u32,
+    pub screenshot_history_thumbnails: u32,
+    pub screenshot_thumbnail_width: u32,
+    /// Long-term memory tools, and how many relevant memories are added to the
+    /// task message of a new run.
+    pub memory_enabled: bool,
+    pub memory_inject_count: u32,
+    /// System prompt template with `{{os}}`-style variables; empty uses the built-in one.
+    pub system_prompt_template: String,
+    /// Added to every system prompt.
+    pub custom_instructions: String,
+    /// Prompt template and instructions per provider, keyed by a built-in provider id
+    /// or a custom profile's id.
+    pub provider_prompts: HashMap<String, PromptOverride>,
     pub setup_complete: bool,
 }
 
@@ -35,23 +127,85 @@ impl Default for Settings {
             openrouter_api_key: String::new(),
             ollama_api_key: String::new(),
             ollama_base_url: "http://127.0.0.1:11434".to_string(),
+            ollama_options: OllamaOptions::default(),
+            gemini_api_key: String::new(),
+            gemini_base_url: DEFAULT_GEMINI_BASE_URL.to_string(),
+            custom_providers: Vec::new(),
             provider: "anthropic".to_string(),
             model: "claude-sonnet-4-20250514".to_string(),
+            fallback_providers: Vec::new(),
             display_width: 1280,
             display_height: 800,
             shell_timeout_secs: 30,
+            thinking_budget_tokens: 0,
+            reasoning_effort: String::new(),
+            retry_max_attempts: 4,
+            retry_max_delay_secs: 60,
+            model_prices: HashMap::new(),
+            max_tokens_per_task: 0,
+            max_cost_per_task_usd: 0.0,
+            max_iterations: 100,
+            max_run_duration_secs: 1800,
+            max_actions: HashMap::new(),
+            approval_policies: default_approval_policies(),
+            approval_timeout_secs: 120,
+            stuck_repeat_threshold: 3,
+            stuck_unchanged_threshold: 5,
+            stuck_max_nudges: 2,
+            completion_reminders: 2,
+            compaction_threshold_tokens: 100_000,
+            compaction_keep_recent: 6,
+            screenshot_history_full: 5,
+            screenshot_history_thumbnails: 5,
+            screenshot_thumbnail_width: 320,
+            memory_enabled: true,
+            memory_inject_count: 3,
+            system_prompt_template: String::new(),
+            custom_instructions: String::new(),
+            provider_prompts: HashMap::new(),
             setup_complete: false,
         }
     }
 }
 
+impl Settings {
+    /// Settings the frontend saved in an earlier session. The frontend only syncs its
+    /// copy once its window has loaded, and work can start before that (a schedule
+    /// due at startup), so the backend reads them itself on startup.
+    pub fn load(app: &AppHandle) -> AppResult<Option<Self>> {
+        let store = app
+            .store(SETTINGS_STORE_FILE)
+            .map_err(|e| AppError::Settings(e.to_string()))?;
+        store
+            .get(SETTINGS_STORE_KEY)
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| AppError::Serialization(e.to_string()))
+    }
+}
+
 #[derive(Debug)]
 pub struct AgentState {
     pub status: AgentStatus,
     pub current_task: Option<String>,
     pub iteration: u32,
     pub estimated_seconds: Option<u32>,
+    pub usage: RunUsage,
+    /// Set when the last run ended on a run limit.
+    pub limit_reached: Option<LimitHit>,
+    /// Id of the current (or last) run in the run store.
+    pub run_id: Option<String>,
+    /// Outcome of the last finished run.
+    pub task_result: Option<TaskResult>,
     pub cancel_token: Option<CancellationToken>,
+    pub pause_token: Option<PauseToken>,
+    /// Follow-up user messages waiting for the next iteration boundary.
+    pub pending_messages: Vec<String>,
+    /// The action currently waiting on the user, if any.
+    pub pending_approval: Option<PendingApproval>,
+    /// Bumped by every launch. A stopped run can still be unwinding when the next one
+    /// starts, so its cleanup only touches this state while the number still matches.
+    pub generation: u64,
 }
 
 impl Default for AgentState {
@@ -61,7 +215,15 @@ impl Default for AgentState {
             current_task: None,
             iteration: 0,
             estimated_seconds: None,
+            usage: RunUsage::default(),
+            limit_reached: None,
+            run_id: None,
+            task_result: None,
             cancel_token: None,
+            pause_token: None,
+            pending_messages: Vec::new(),
+            pending_approval: None,
+            generation: 0,
         }
     }
 }
@@ -69,6 +231,14 @@ impl Default for AgentState {
 pub struct AppState {
     pub agent: Arc<Mutex<AgentState>>,
     pub settings: Arc<RwLock<Settings>>,
+    pub providers: Arc<ProviderRegistry>,
+    pub queue: Arc<Mutex<TaskQueue>>,
+    /// Woken whenever the next queued task may be able to start.
+    pub queue_notify: Arc<Notify>,
+    pub schedules: Arc<Mutex<Schedules>>,
+    /// Wakes the scheduler timer after schedules change.
+    pub schedule_notify: Arc<Notify>,
+    pub memory: Arc<Mutex<MemoryStore>>,
 }
 
 impl AppState {
@@ -76,6 +246,12 @@ impl AppState {
         Self {
             agent: Arc::new(Mutex::new(AgentState::default())),
             settings: Arc::new(RwLock::new(Settings::default())),
+            providers: Arc::new(ProviderRegistry::with_builtin()),
+            queue: Arc::new(Mutex::new(TaskQueue::default())),
+            queue_notify: Arc::new(Notify::new()),
+            schedules: Arc::new(Mutex::new(Schedules::default())),
+            schedule_notify: Arc::new(Notify::new()),
+            memory: Arc::new(Mutex::new(MemoryStore::default())),
         }
     }
 }
@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::{AppError, AppResult};
+
+/// Read `file` from the app data directory, or the default if it doesn't exist yet.
+pub fn load<T: DeserializeOwned + Default>(app: &AppHandle, file: &str) -> AppResult<T> {
+    let path = app.path().app_data_dir()?.join(file);
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| AppError::Serialization(e.to_string()))
+}
+
+/// Write `value` to `path` as pretty JSON. It goes to a temp file that is then
+/// renamed into place, so a crash never leaves a half-written file.
+pub fn write_atomic<T: Serialize + ?Sized>(path: &Path, value: &T) -> AppResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json =
+        serde_json::to_string_pretty(value).map_err(|e| AppError::Serialization(e.to_string()))?;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
+
+/// Save `value` to `file` in the app data directory and emit `event` with it so the
+/// frontend sees the change. A failed save is logged rather than returned, since the
+/// copy in memory is still correct; `what` names the data in that warning.
+pub fn persist<T: Serialize + ?Sized>(
+    app: &AppHandle,
+    file: &str,
+    event: &str,
+    what: &str,
+    value: &T,
+) {
+    let saved = app
+        .path()
+        .app_data_dir()
+        .map_err(AppError::from)
+        .and_then(|dir| write_atomic(&dir.join(file), value));
+    if let Err(e) = saved {
+        log::warn!("Failed to save {}: {}", what, e);
+    }
+    let _ = app.emit(event, value);
+}
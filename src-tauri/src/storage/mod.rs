@@ -0,0 +1,2 @@
+pub mod json_file;
+pub mod runs;
@@ -0,0 +1,512 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::agent::action::AgentAction;
+use crate::agent::completion::TaskResult;
+use crate::agent::history::screenshot_owner;
+use crate::ai::types::{ContentBlock, Message};
+use crate::error::{AppError, AppResult};
+use crate::state::Settings;
+use crate::storage::json_file;
+
+const META_FILE: &str = "meta.json";
+const SETTINGS_FILE: &str = "settings.json";
+const TRANSCRIPT_FILE: &str = "transcript.jsonl";
+const SCREENSHOTS_DIR: &str = "screenshots";
+
+/// `ImageSource::source_type` for screenshots stored next to the transcript; `data`
+/// then holds the path relative to the run directory.
+const FILE_SOURCE: &str = "file";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    /// Ended through `task_complete` (or the completion fallback).
+    Finished,
+    Failed,
+    Cancelled,
+    LimitReached,
+    /// Ended for another reason, e.g. a budget.
+    Stopped,
+    /// Still marked running when the app started, so the app died mid-run.
+    Interrupted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunMeta {
+    pub id: String,
+    pub task: String,
+    pub provider: String,
+    pub model: String,
+    pub status: RunStatus,
+    pub started_at: String,
+    pub updated_at: String,
+    pub ended_at: Option<String>,
+    pub result: Option<TaskResult>,
+    pub error: Option<String>,
+}
+
+/// One line of `transcript.jsonl`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptEntry {
+    Message {
+        timestamp: String,
+        message: Message,
+    },
+    Action {
+        timestamp: String,
+        iteration: u32,
+        kind: String,
+        description: String,
+        /// Tool call the action came from, linking it to its result and screenshot.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tool_use_id: Option<String>,
+        /// The action as executed, for replays.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        action: Option<AgentAction>,
+    },
+}
+
+/// An executed action from a stored run, with the screenshot it produced if it was a
+/// screenshot action.
+pub struct RecordedAction {
+    pub action: AgentAction,
+    /// JPEG bytes of the original screenshot.
+    pub screenshot: Option<Vec<u8>>,
+}
+
+/// A run as stored on disk. Screenshots stay as file references.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunRecord {
+    pub meta: RunMeta,
+    /// Settings the run started with, API keys removed.
+    pub settings: Value,
+    pub entries: Vec<TranscriptEntry>,
+}
+
+pub fn runs_dir(app: &AppHandle) -> AppResult<PathBuf> {
+    Ok(app.path().app_data_dir()?.join("runs"))
+}
+
+/// Resolve a run directory, refusing ids that could escape `runs_dir`.
+fn run_dir(runs_dir: &Path, id: &str) -> AppResult<PathBuf> {
+    if uuid::Uuid::parse_str(id).is_err() {
+        return Err(AppError::Agent(format!("Invalid run id: {}", id)));
+    }
+    let dir = runs_dir.join(id);
+    if !dir.is_dir() {
+        return Err(AppError::Agent(format!("Run not found: {}", id)));
+    }
+    Ok(dir)
+}
+
+fn read_meta(dir: &Path) -> AppResult<RunMeta> {
+    let raw = fs::read_to_string(dir.join(META_FILE))?;
+    serde_json::from_str(&raw).map_err(|e| AppError::Serialization(e.to_string()))
+}
+
+fn write_meta(dir: &Path, meta: &RunMeta) -> AppResult<()> {
+    json_file::write_atomic(&dir.join(META_FILE), meta)
+}
+
+fn read_transcript(dir: &Path) -> AppResult<Vec<TranscriptEntry>> {
+    let path = dir.join(TRANSCRIPT_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            // A crash can cut the last line short; everything before it is still good
+            Err(e) => log::warn!("Skipping unreadable transcript line: {}", e),
+        }
+    }
+    Ok(entries)
+}
+
+/// Settings as JSON with every API key and custom header value blanked, for the
+/// run's snapshot. Header names are kept; their values often carry credentials.
+fn redacted_settings(settings: &Settings) -> Value {
+    fn redact(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, v) in map.iter_mut() {
+                    if key.ends_with("api_key") {
+                        *v = Value::String(String::new());
+                    } else if key == "extra_headers" {
+                        if let Value::Object(headers) = v {
+                            headers
+                                .values_mut()
+                                .for_each(|header| *header = Value::String(String::new()));
+                        }
+                    } else {
+                        redact(v);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(redact),
+            _ => {}
+        }
+    }
+    let mut value = serde_json::to_value(settings).unwrap_or(Value::Null);
+    redact(&mut value);
+    value
+}
+
+pub fn list_runs(runs_dir: &Path) -> AppResult<Vec<RunMeta>> {
+    if !runs_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut runs: Vec<RunMeta> = fs::read_dir(runs_dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| match read_meta(&entry.path()) {
+            Ok(meta) => Some(meta),
+            Err(e) => {
+                log::warn!("Skipping run {:?}: {}", entry.file_name(), e);
+                None
+            }
+        })
+        .collect();
+    runs.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    Ok(runs)
+}
+
+pub fn get_run(runs_dir: &Path, id: &str) -> AppResult<RunRecord> {
+    let dir = run_dir(runs_dir, id)?;
+    let settings = fs::read_to_string(dir.join(SETTINGS_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or(Value::Null);
+    Ok(RunRecord {
+        meta: read_meta(&dir)?,
+        settings,
+        entries: read_transcript(&dir)?,
+    })
+}
+
+pub fn delete_run(runs_dir: &Path, id: &str) -> AppResult<()> {
+    let dir = run_dir(runs_dir, id)?;
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+/// The actions a stored run executed, in order, for replaying it.
+pub fn recorded_actions(runs_dir: &Path, id: &str) -> AppResult<Vec<RecordedAction>> {
+    let dir = run_dir(runs_dir, id)?;
+    let entries = read_transcript(&dir)?;
+
+    // Screenshots live in the message holding the matching tool result
+    let mut screenshots: HashMap<String, String> = HashMap::new();
+    for entry in &entries {
+        let TranscriptEntry::Message { message, .. } = entry else {
+            continue;
+        };
+        for (i, block) in message.content.iter().enumerate() {
+            let ContentBlock::Image { source } = block else {
+                continue;
+            };
+            if source.source_type != FILE_SOURCE {
+                continue;
+            }
+            if let Some(id) = screenshot_owner(&message.content, i) {
+                screenshots.insert(id.to_string(), source.data.clone());
+            }
+        }
+    }
+
+    let actions: Vec<RecordedAction> = entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            TranscriptEntry::Action {
+                action: Some(action),
+                tool_use_id,
+                ..
+            } => {
+                let screenshot = tool_use_id
+                    .and_then(|id| screenshots.get(&id))
+                    .and_then(|relative| fs::read(dir.join(relative)).ok());
+                Some(RecordedAction { action, screenshot })
+            }
+            _ => None,
+        })
+        .collect();
+    if actions.is_empty() {
+        return Err(AppError::Agent(format!(
+            "Run {} has no recorded actions to replay",
+            id
+        )));
+    }
+    Ok(actions)
+}
+
+/// Mark runs left in `running` by a previous session as interrupted. Called once at
+/// startup, before any run can have started.
+pub fn mark_interrupted_runs(runs_dir: &Path) -> AppResult<Vec<RunMeta>> {
+    let mut interrupted = Vec::new();
+    for mut meta in list_runs(runs_dir)? {
+        if meta.status != RunStatus::Running {
+            continue;
+        }
+        meta.status = RunStatus::Interrupted;
+        write_meta(&runs_dir.join(&meta.id), &meta)?;
+        interrupted.push(meta);
+    }
+    Ok(interrupted)
+}
+
+/// Appends a run's transcript to disk as it happens, so a crash loses at most the
+/// current iteration.
+pub struct RunRecorder {
+    dir: PathBuf,
+    meta: RunMeta,
+    /// Messages already written; earlier ones are never rewritten.
+    persisted: usize,
+    screenshots: usize,
+}
+
+impl RunRecorder {
+    pub fn create(runs_dir: &Path, task: &str, settings: &Settings) -> AppResult<Self> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let dir = runs_dir.join(&id);
+        fs::create_dir_all(dir.join(SCREENSHOTS_DIR))?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let meta = RunMeta {
+            id,
+            task: task.to_string(),
+            provider: settings.provider.clone(),
+            model: settings.model.clone(),
+            status: RunStatus::Running,
+            started_at: now.clone(),
+            updated_at: now,
+            ended_at: None,
+            result: None,
+            error: None,
+        };
+        write_meta(&dir, &meta)?;
+        let snapshot = serde_json::to_string_pretty(&redacted_settings(settings))
+            .map_err(|e| AppError::Serialization(e.to_string()))?;
+        fs::write(dir.join(SETTINGS_FILE), snapshot)?;
+
+        Ok(Self {
+            dir,
+            meta,
+            persisted: 0,
+            screenshots: 0,
+        })
+    }
+
+    /// Reopen a stored run to continue it, returning the conversation with its
+    /// screenshots loaded back in.
+    pub fn reopen(runs_dir: &Path, id: &str) -> AppResult<(Self, Vec<Message>)> {
+        let dir = run_dir(runs_dir, id)?;
+        let mut meta = read_meta(&dir)?;
+        if meta.status == RunStatus::Running {
+            return Err(AppError::Agent(format!("Run {} is still running", id)));
+        }
+
+        let mut messages: Vec<Message> = read_transcript(&dir)?
+            .into_iter()
+            .filter_map(|entry| match entry {
+                TranscriptEntry::Message { message, .. } => Some(message),
+                TranscriptEntry::Action { .. } => None,
+            })
+            .collect();
+        if messages.is_empty() {
+            return Err(AppError::Agent(format!("Run {} has no transcript", id)));
+        }
+        // Turns added on resume are stored separately; fold them back into their turn
+        messages.dedup_by(|next, prev| {
+            if next.role != prev.role {
+                return false;
+            }
+            prev.content.append(&mut next.content);
+            true
+        });
+        for message in &mut messages {
+            load_screenshots(&dir, message);
+        }
+        let screenshots = fs::read_dir(dir.join(SCREENSHOTS_DIR))
+            .map(|entries| entries.count())
+            .unwrap_or(0);
+
+        meta.status = RunStatus::Running;
+        meta.ended_at = None;
+        meta.result = None;
+        meta.error = None;
+        meta.updated_at = chrono::Utc::now().to_rfc3339();
+        write_meta(&dir, &meta)?;
+
+        let persisted = messages.len();
+        Ok((
+            Self {
+                dir,
+                meta,
+                persisted,
+                screenshots,
+            },
+            messages,
+        ))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.meta.id
+    }
+
+    /// Write messages that have become final since the last call. Only call this when
+    /// no earlier message will change again, i.e. right before a provider request and
+    /// once the run ends.
+    pub fn sync_messages(&mut self, messages: &[Message]) {
+        if messages.len() <= self.persisted {
+            return;
+        }
+        for message in &messages[self.persisted..] {
+            self.write_message(message);
+        }
+        self.persisted = messages.len();
+        self.touch();
+    }
+
+    /// Add blocks to the user turn at the end of `messages` (starting one if needed).
+    /// They are stored as a turn of their own, since lines already written are never
+    /// rewritten, and merged back in by `reopen`.
+    pub fn append_user_turn(&mut self, messages: &mut Vec<Message>, blocks: Vec<ContentBlock>) {
+        self.sync_messages(messages);
+        let turn = Message {
+            role: "user".to_string(),
+            content: blocks,
+        };
+        self.write_message(&turn);
+        self.touch();
+        match messages.last_mut() {
+            Some(last) if last.role == "user" => last.content.extend(turn.content),
+            _ => messages.push(turn),
+        }
+        self.persisted = messages.len();
+    }
+
+    /// Note that `messages` was rewritten after being synced, by compaction or repairs.
+    /// The transcript keeps the history as it was; only messages added from here on
+    /// are new.
+    pub fn mark_rewritten(&mut self, messages: &[Message]) {
+        self.persisted = messages.len();
+    }
+
+    pub fn record_action(&mut self, iteration: u32, tool_use_id: &str, action: &AgentAction) {
+        self.append(&TranscriptEntry::Action {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            iteration,
+            kind: action.kind().to_string(),
+            description: action.description(),
+            tool_use_id: Some(tool_use_id.to_string()),
+            action: Some(action.clone()),
+        });
+    }
+
+    pub fn finish(&mut self, status: RunStatus, result: Option<TaskResult>, error: Option<String>) {
+        let now = chrono::Utc::now().to_rfc3339();
+        self.meta.status = status;
+        self.meta.result = result;
+        self.meta.error = error;
+        self.meta.updated_at = now.clone();
+        self.meta.ended_at = Some(now);
+        if let Err(e) = write_meta(&self.dir, &self.meta) {
+            log::warn!("Failed to finalize run {}: {}", self.meta.id, e);
+        }
+    }
+
+    fn write_message(&mut self, message: &Message) {
+        let message = self.externalize_screenshots(message);
+        self.append(&TranscriptEntry::Message {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            message,
+        });
+    }
+
+    fn touch(&mut self) {
+        self.meta.updated_at = chrono::Utc::now().to_rfc3339();
+        if let Err(e) = write_meta(&self.dir, &self.meta) {
+            log::warn!("Failed to update run metadata: {}", e);
+        }
+    }
+
+    fn append(&self, entry: &TranscriptEntry) {
+        let result = serde_json::to_string(entry)
+            .map_err(|e| AppError::Serialization(e.to_string()))
+            .and_then(|line| {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.dir.join(TRANSCRIPT_FILE))?;
+                writeln!(file, "{}", line)?;
+                Ok(())
+            });
+        if let Err(e) = result {
+            log::warn!("Failed to write run transcript: {}", e);
+        }
+    }
+
+    /// Copy of `message` with inline screenshots saved as files and replaced by references.
+    fn externalize_screenshots(&mut self, message: &Message) -> Message {
+        let mut message = message.clone();
+        for block in &mut message.content {
+            let ContentBlock::Image { source } = block else {
+                continue;
+            };
+            if source.source_type != "base64" {
+                continue;
+            }
+            let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(&source.data) else {
+                continue;
+            };
+            self.screenshots += 1;
+            let relative = format!("{}/{:05}.jpg", SCREENSHOTS_DIR, self.screenshots);
+            match fs::write(self.dir.join(&relative), bytes) {
+                Ok(()) => {
+                    source.source_type = FILE_SOURCE.to_string();
+                    source.data = relative;
+                }
+                Err(e) => log::warn!("Failed to save screenshot: {}", e),
+            }
+        }
+        message
+    }
+}
+
+/// Inline screenshots referenced from a stored message, dropping any that are missing.
+fn load_screenshots(dir: &Path, message: &mut Message) {
+    message.content.retain_mut(|block| {
+        let ContentBlock::Image { source } = block else {
+            return true;
+        };
+        if source.source_type != FILE_SOURCE {
+            return true;
+        }
+        match fs::read(dir.join(&source.data)) {
+            Ok(bytes) => {
+                source.source_type = "base64".to_string();
+                source.data = base64::engine::general_purpose::STANDARD.encode(bytes);
+                true
+            }
+            Err(e) => {
+                log::warn!("Dropping missing screenshot {}: {}", source.data, e);
+                false
+            }
+        }
+    });
+}
@@ -4,7 +4,15 @@ import { useUIStore } from "./stores/uiStore";
 import { useAgentStore } from "./stores/agentStore";
 import { useSettingsStore } from "./stores/settingsStore";
 import { EVENTS } from "./lib/events";
-import { restoreDefaultWindow } from "./lib/windowManager";
+import type {
+  ApprovalRequest,
+  FallbackProvider,
+  LimitHit,
+  RunUsage,
+  StuckDetection,
+  TaskResult,
+} from "./lib/types";
+import { restoreDefaultWindow, shrinkToTaskbar } from "./lib/windowManager";
 import { StartScreen } from "./components/StartScreen";
 import { Taskbar } from "./components/Taskbar";
 import { TaskbarExpanded } from "./components/TaskbarExpanded";
@@ -18,13 +26,21 @@ function App() {
   const addToast = useUIStore((s) => s.addToast);
   const expanded = useUIStore((s) => s.expanded);
   const setMode = useUIStore((s) => s.setMode);
+  const setExpanded = useUIStore((s) => s.setExpanded);
   const { settings, loaded, updateSettings, saveSettings } = useSettingsStore();
   const {
     setStatus,
+    setTask,
     addAction,
     setThinking,
+    appendThinkingDelta,
     setMessage,
+    appendMessageDelta,
+    endMessageStream,
+    discardMessageStream,
     setEstimatedSeconds,
+    setUsage,
+    setPendingApproval,
     setScreenshot,
     setCursor,
     reset,
@@ -64,6 +80,12 @@ function App() {
         reset();
       } else if (payload === "running") {
         setStatus("running");
+      } else if (payload === "paused") {
+        setStatus("paused");
+      } else if (payload === "limit_reached") {
+        setStatus("limit_reached");
+        setMode("start");
+        restoreDefaultWindow();
       } else if (payload.startsWith("error:")) {
         setStatus("error");
         const errMsg = payload.slice(6);
@@ -94,14 +116,59 @@ function App() {
       setThinking("");
     }).then((u) => unlisten.push(u));
 
-    listen<{ text: string }>(EVENTS.AGENT_THINKING, (e) => {
-      setThinking(e.payload.text);
+    listen<{
+      summarized: number;
+      tokens_before: number;
+      tokens_after: number;
+      timestamp: string;
+      iteration: number;
+    }>(EVENTS.CONTEXT_COMPACTED, (e) => {
+      const { summarized, tokens_before, tokens_after, timestamp, iteration } =
+        e.payload;
+      addAction({
+        id: crypto.randomUUID(),
+        action_type: "compaction",
+        description: `Condensed ${summarized} earlier messages (~${tokens_before.toLocaleString()} → ~${tokens_after.toLocaleString()} tokens)`,
+        timestamp,
+        iteration,
+      });
+    }).then((u) => unlisten.push(u));
+
+    listen<{ text: string; delta?: boolean }>(EVENTS.AGENT_THINKING, (e) => {
+      if (e.payload.delta) {
+        appendThinkingDelta(e.payload.text);
+      } else {
+        setThinking(e.payload.text);
+      }
+      endMessageStream();
     }).then((u) => unlisten.push(u));
 
     listen<{ text: string }>(EVENTS.AGENT_MESSAGE, (e) => {
       setMessage(e.payload.text);
     }).then((u) => unlisten.push(u));
 
+    listen<{ text: string }>(EVENTS.AGENT_MESSAGE_DELTA, (e) => {
+      appendMessageDelta(e.payload.text);
+    }).then((u) => unlisten.push(u));
+
+    // A failed attempt's partial reply is about to be streamed again from the start
+    listen(EVENTS.AGENT_STREAM_RESET, () => {
+      discardMessageStream();
+    }).then((u) => unlisten.push(u));
+
+    listen<{
+      attempt: number;
+      max_attempts: number;
+      delay_ms: number;
+      error: string;
+    }>(EVENTS.AGENT_RETRYING, (e) => {
+      const { attempt, max_attempts, delay_ms } = e.payload;
+      setThinking(
+        `Provider error, retrying in ${Math.ceil(delay_ms / 1000)}s (${attempt}/${max_attempts})...`,
+      );
+      endMessageStream();
+    }).then((u) => unlisten.push(u));
+
     listen<{ x: number; y: number }>(EVENTS.CURSOR_MOVED, (e) => {
       setCursor(e.payload.x, e.payload.y);
     }).then((u) => unlisten.push(u));
@@ -110,6 +177,116 @@ function App() {
       setEstimatedSeconds(e.payload.seconds_remaining);
     }).then((u) => unlisten.push(u));
 
+    listen<{
+      from: FallbackProvider;
+      to: FallbackProvider;
+      reason: string;
+    }>(EVENTS.PROVIDER_SWITCHED, (e) => {
+      const { to } = e.payload;
+      addToast(`Switched to ${to.provider} / ${to.model}`, "info");
+      setThinking(`Now using ${to.provider} / ${to.model}...`);
+      endMessageStream();
+    }).then((u) => unlisten.push(u));
+
+    listen<LimitHit>(EVENTS.LIMIT_REACHED, (e) => {
+      if (e.payload.screenshot) setScreenshot(e.payload.screenshot);
+      // budget-exceeded already reported this one
+      if (e.payload.limit !== "budget") addToast(e.payload.message, "info");
+    }).then((u) => unlisten.push(u));
+
+    listen<RunUsage>(EVENTS.USAGE_UPDATED, (e) => {
+      setUsage(e.payload);
+    }).then((u) => unlisten.push(u));
+
+    listen<{ reason: string }>(EVENTS.BUDGET_EXCEEDED, (e) => {
+      addToast(e.payload.reason, "error");
+    }).then((u) => unlisten.push(u));
+
+    listen<TaskResult>(EVENTS.TASK_FINISHED, (e) => {
+      const { status, summary } = e.payload;
+      const label: Record<TaskResult["status"], string> = {
+        success: "Task completed",
+        failure: "Task failed",
+        blocked: "Task blocked",
+        unknown: "Agent stopped",
+      };
+      addToast(
+        `${label[status]}: ${summary}`,
+        status === "success" ? "success" : status === "failure" ? "error" : "info",
+      );
+    }).then((u) => unlisten.push(u));
+
+    listen<{ step: number; total: number; diff: number; passed: boolean }>(
+      EVENTS.REPLAY_CHECKPOINT,
+      (e) => {
+        const { step, total, diff, passed } = e.payload;
+        setThinking(
+          `Replay checkpoint ${step}/${total}: ${passed ? "matches" : "diverged"} (diff ${diff.toFixed(3)})`,
+        );
+      },
+    ).then((u) => unlisten.push(u));
+
+    listen<{ status: string }>(EVENTS.REPLAY_FINISHED, (e) => {
+      const messages: Record<string, [string, "success" | "error" | "info"]> = {
+        completed: ["Replay finished.", "success"],
+        cancelled: ["Replay stopped.", "info"],
+        diverged: ["Replay stopped: the screen no longer matches the recording.", "error"],
+        handed_off: ["Replay diverged; handing the task to the model.", "info"],
+      };
+      const [text, kind] = messages[e.payload.status] ?? ["Replay ended.", "info"];
+      addToast(text, kind);
+    }).then((u) => unlisten.push(u));
+
+    // The queue or a schedule started a run without the start screen
+    const showBackgroundRun = (task: string) => {
+      reset();
+      setTask(task);
+      shrinkToTaskbar();
+      setMode("taskbar");
+    };
+
+    listen<{ id: string; task: string }>(EVENTS.QUEUE_TASK_STARTED, (e) => {
+      showBackgroundRun(e.payload.task);
+    }).then((u) => unlisten.push(u));
+
+    listen<{ id: string; name: string; task: string; outcome: string }>(
+      EVENTS.SCHEDULE_FIRED,
+      (e) => {
+        const { name, task, outcome } = e.payload;
+        if (outcome === "started") {
+          showBackgroundRun(task);
+        } else if (outcome === "queued") {
+          addToast(`Scheduled task "${name}" queued`, "info");
+        } else if (outcome.startsWith("failed")) {
+          addToast(`Scheduled task "${name}" ${outcome}`, "error");
+        }
+      },
+    ).then((u) => unlisten.push(u));
+
+    listen<StuckDetection>(EVENTS.STUCK_DETECTED, (e) => {
+      const what =
+        e.payload.kind === "repeated_action"
+          ? `repeated the same action ${e.payload.count} times`
+          : `made ${e.payload.count} actions without the screen changing`;
+      addToast(
+        e.payload.escalated
+          ? `Agent ${what}; paused so you can step in.`
+          : `Agent ${what}; nudging it to try something else.`,
+        e.payload.escalated ? "error" : "info",
+      );
+    }).then((u) => unlisten.push(u));
+
+    listen<ApprovalRequest>(EVENTS.APPROVAL_REQUESTED, (e) => {
+      setPendingApproval(e.payload);
+      // The prompt lives in the expanded taskbar
+      setExpanded(true);
+    }).then((u) => unlisten.push(u));
+
+    listen<{ id: string }>(EVENTS.APPROVAL_RESOLVED, (e) => {
+      const pending = useAgentStore.getState().pendingApproval;
+      if (pending?.id === e.payload.id) setPendingApproval(null);
+    }).then((u) => unlisten.push(u));
+
     return () => {
       unlisten.forEach((u) => u());
     };
@@ -3,6 +3,9 @@ import {
   Terminal,
   FileText,
   Clock,
+  User,
+  Minimize2,
+  Brain,
 } from "lucide-react";
 import type { ActionEntry as ActionEntryType } from "../lib/types";
 
@@ -10,6 +13,11 @@ const iconMap: Record<string, React.ReactNode> = {
   computer: <MousePointer2 className="w-3.5 h-3.5 text-blue-400" />,
   bash: <Terminal className="w-3.5 h-3.5 text-green-400" />,
   text_editor: <FileText className="w-3.5 h-3.5 text-yellow-400" />,
+  user: <User className="w-3.5 h-3.5 text-purple-400" />,
+  compaction: <Minimize2 className="w-3.5 h-3.5 text-zinc-400" />,
+  memory_search: <Brain className="w-3.5 h-3.5 text-pink-400" />,
+  memory_read: <Brain className="w-3.5 h-3.5 text-pink-400" />,
+  memory_write: <Brain className="w-3.5 h-3.5 text-pink-400" />,
 };
 
 function getRelativeTime(timestamp: string): string {
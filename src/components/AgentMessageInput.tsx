@@ -0,0 +1,42 @@
+import { useState } from "react";
+import { SendHorizontal } from "lucide-react";
+import { commands } from "../lib/commands";
+import { useUIStore } from "../stores/uiStore";
+
+export function AgentMessageInput() {
+  const [text, setText] = useState("");
+  const addToast = useUIStore((s) => s.addToast);
+
+  const handleSend = async () => {
+    const trimmed = text.trim();
+    if (!trimmed) return;
+    try {
+      await commands.sendAgentMessage(trimmed);
+      setText("");
+    } catch (e) {
+      addToast(`Failed to send message: ${e}`, "error");
+    }
+  };
+
+  return (
+    <div className="flex items-center gap-2 px-3 py-2 border-t border-white/10 shrink-0">
+      <input
+        value={text}
+        onChange={(e) => setText(e.target.value)}
+        onKeyDown={(e) => {
+          if (e.key === "Enter") handleSend();
+        }}
+        placeholder="Send a follow-up to the agent..."
+        className="flex-1 bg-zinc-800/60 border border-zinc-700/60 rounded-lg px-3 py-1.5 text-xs text-zinc-100 placeholder:text-zinc-500 focus:outline-none focus:ring-1 focus:ring-blue-500/40"
+      />
+      <button
+        onClick={handleSend}
+        disabled={!text.trim()}
+        className="p-1.5 rounded-md text-zinc-400 hover:text-zinc-200 hover:bg-white/[0.06] disabled:opacity-40 transition-colors"
+        title="Send message"
+      >
+        <SendHorizontal className="w-4 h-4" />
+      </button>
+    </div>
+  );
+}
@@ -0,0 +1,77 @@
+import { useState } from "react";
+import { ShieldAlert } from "lucide-react";
+import { commands } from "../lib/commands";
+import { useAgentStore } from "../stores/agentStore";
+import { useUIStore } from "../stores/uiStore";
+
+/** Pull the part of an action worth reviewing: the command, or the file and its new text. */
+function detailText(details: Record<string, unknown>): string | null {
+  const parts = [details.command, details.path, details.content, details.new_text]
+    .filter((v): v is string => typeof v === "string" && v.length > 0);
+  return parts.length > 0 ? parts.join("\n\n") : null;
+}
+
+export function ApprovalPrompt() {
+  const request = useAgentStore((s) => s.pendingApproval);
+  const setPendingApproval = useAgentStore((s) => s.setPendingApproval);
+  const addToast = useUIStore((s) => s.addToast);
+  const [reason, setReason] = useState("");
+
+  if (!request) return null;
+
+  const details = detailText(request.details);
+
+  const respond = async (approved: boolean) => {
+    try {
+      if (approved) {
+        await commands.approveAction(request.id);
+      } else {
+        await commands.rejectAction(request.id, reason.trim());
+      }
+    } catch (e) {
+      addToast(`Failed to respond to approval: ${e}`, "error");
+    }
+    setPendingApproval(null);
+    setReason("");
+  };
+
+  return (
+    <div className="px-4 py-3 border-b border-amber-500/20 bg-amber-500/5 shrink-0 animate-fade-in">
+      <div className="flex items-center gap-2 mb-1.5">
+        <ShieldAlert className="w-4 h-4 text-amber-400 shrink-0" />
+        <p className="text-xs text-zinc-100 font-medium truncate">
+          Approve {request.action_type}?
+        </p>
+        <span className="ml-auto text-[10px] text-zinc-500 shrink-0">
+          auto-rejects in {request.timeout_secs}s
+        </span>
+      </div>
+      <p className="text-[11px] text-zinc-300 break-words">{request.description}</p>
+      {details && (
+        <pre className="mt-1.5 max-h-24 overflow-auto rounded-md bg-zinc-950 border border-zinc-800 px-2 py-1.5 text-[10px] text-zinc-400 font-mono whitespace-pre-wrap break-words">
+          {details}
+        </pre>
+      )}
+      <input
+        value={reason}
+        onChange={(e) => setReason(e.target.value)}
+        placeholder="Reason for rejecting (optional, shown to the agent)"
+        className="mt-2 w-full bg-zinc-950 border border-zinc-800 rounded-lg px-2.5 py-1.5 text-[11px] text-zinc-300 placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+      />
+      <div className="flex justify-end gap-2 mt-2">
+        <button
+          onClick={() => respond(false)}
+          className="px-3 py-1 rounded-md text-[11px] text-zinc-300 bg-zinc-800 hover:bg-zinc-700 transition-colors btn-press"
+        >
+          Reject
+        </button>
+        <button
+          onClick={() => respond(true)}
+          className="px-3 py-1 rounded-md text-[11px] text-white bg-blue-600 hover:bg-blue-500 transition-colors btn-press"
+        >
+          Approve
+        </button>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,175 @@
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { Brain, Pencil, Plus, Trash2 } from "lucide-react";
+import { clsx } from "clsx";
+import { commands } from "../lib/commands";
+import { EVENTS } from "../lib/events";
+import type { MemoryEntry } from "../lib/types";
+import { useUIStore } from "../stores/uiStore";
+
+const inputClass =
+  "w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-1.5 text-xs text-white placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700";
+
+/** Long-term memory the agent keeps across tasks; the user can search, add, edit and delete entries. */
+export function Memories() {
+  const [memories, setMemories] = useState<MemoryEntry[]>([]);
+  const [query, setQuery] = useState("");
+  const [results, setResults] = useState<MemoryEntry[] | null>(null);
+  // Id of the memory being edited, "new" for a new one
+  const [editing, setEditing] = useState<string | null>(null);
+  const [title, setTitle] = useState("");
+  const [content, setContent] = useState("");
+  const [tags, setTags] = useState("");
+  const addToast = useUIStore((s) => s.addToast);
+
+  useEffect(() => {
+    commands
+      .listMemories()
+      .then(setMemories)
+      .catch((e) => console.error("Failed to list memories:", e));
+
+    const unlisten = listen<MemoryEntry[]>(EVENTS.MEMORIES_UPDATED, (e) =>
+      setMemories(e.payload),
+    );
+    return () => {
+      unlisten.then((u) => u());
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!query.trim()) {
+      setResults(null);
+      return;
+    }
+    commands
+      .searchMemories(query)
+      .then(setResults)
+      .catch((e) => console.error("Failed to search memories:", e));
+  }, [query, memories]);
+
+  const startEdit = (memory: MemoryEntry | null) => {
+    setEditing(memory ? memory.id : "new");
+    setTitle(memory?.title ?? "");
+    setContent(memory?.content ?? "");
+    setTags(memory?.tags.join(", ") ?? "");
+  };
+
+  const handleSave = async () => {
+    try {
+      await commands.saveMemory({
+        id: editing === "new" ? undefined : (editing ?? undefined),
+        title,
+        content,
+        tags: tags.split(","),
+      });
+      setEditing(null);
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
+  const handleDelete = async (memory: MemoryEntry) => {
+    try {
+      await commands.deleteMemory(memory.id);
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
+  const shown = results ?? memories;
+
+  return (
+    <div className="mt-10 w-full max-w-lg animate-slide-up">
+      <div className="flex items-center mb-2">
+        <p className="flex-1 flex items-center gap-1.5 text-zinc-500 text-xs font-medium">
+          <Brain className="w-3.5 h-3.5" />
+          Memory
+        </p>
+        <button
+          onClick={() => (editing ? setEditing(null) : startEdit(null))}
+          className="flex items-center gap-1 px-2 py-0.5 rounded-md text-[11px] text-zinc-400 hover:text-zinc-200 hover:bg-zinc-800 transition-colors"
+          title="Add something the agent should know in every task"
+        >
+          <Plus className="w-3 h-3" />
+          Add memory
+        </button>
+      </div>
+
+      {editing && (
+        <div className="flex flex-col gap-2 p-3 mb-2 bg-zinc-900/50 border border-zinc-800/50 rounded-lg">
+          <input
+            type="text"
+            value={title}
+            onChange={(e) => setTitle(e.target.value)}
+            placeholder="Title, e.g. VPN client"
+            className={inputClass}
+          />
+          <textarea
+            value={content}
+            onChange={(e) => setContent(e.target.value)}
+            placeholder="What the agent should remember"
+            rows={3}
+            className={inputClass}
+          />
+          <input
+            type="text"
+            value={tags}
+            onChange={(e) => setTags(e.target.value)}
+            placeholder="Tags, comma separated"
+            className={inputClass}
+          />
+          <button
+            onClick={handleSave}
+            disabled={!title.trim() || !content.trim()}
+            className="self-end px-3 py-1 rounded-md text-xs font-medium bg-zinc-50 text-zinc-900 hover:bg-zinc-200 disabled:bg-zinc-700 disabled:text-zinc-500 transition-colors"
+          >
+            Save
+          </button>
+        </div>
+      )}
+
+      {memories.length > 0 && (
+        <input
+          type="text"
+          value={query}
+          onChange={(e) => setQuery(e.target.value)}
+          placeholder="Search memory"
+          className={clsx(inputClass, "mb-2")}
+        />
+      )}
+
+      <div className="flex flex-col gap-1">
+        {shown.map((memory) => (
+          <div
+            key={memory.id}
+            className="flex items-center gap-2 px-3 py-2 bg-zinc-900/50 border border-zinc-800/50 rounded-lg"
+          >
+            <div className="flex-1 min-w-0">
+              <p className="text-xs text-zinc-300 truncate">{memory.title}</p>
+              <p className="text-[10px] text-zinc-500 truncate" title={memory.content}>
+                {memory.content}
+              </p>
+            </div>
+            {memory.run_id && (
+              <span className="text-[10px] text-zinc-600 shrink-0">by agent</span>
+            )}
+            <button
+              onClick={() => startEdit(memory)}
+              className="p-1 rounded-md text-zinc-500 hover:text-zinc-200 hover:bg-zinc-800 transition-colors"
+              title="Edit memory"
+            >
+              <Pencil className="w-3.5 h-3.5" />
+            </button>
+            <button
+              onClick={() => handleDelete(memory)}
+              className="p-1 rounded-md text-zinc-500 hover:text-red-400 hover:bg-zinc-800 transition-colors"
+              title="Delete memory"
+            >
+              <Trash2 className="w-3.5 h-3.5" />
+            </button>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,34 @@
+import { Pause, Play } from "lucide-react";
+import { commands } from "../lib/commands";
+import { useAgentStore } from "../stores/agentStore";
+
+export function PauseButton() {
+  const status = useAgentStore((s) => s.status);
+  const paused = status === "paused";
+
+  const handleToggle = async () => {
+    try {
+      if (paused) {
+        await commands.resumeAgent();
+      } else {
+        await commands.pauseAgent();
+      }
+    } catch (e) {
+      console.error("Failed to toggle pause:", e);
+    }
+  };
+
+  return (
+    <button
+      onClick={handleToggle}
+      className="flex items-center justify-center w-8 h-8 rounded-full bg-zinc-700 hover:bg-zinc-600 transition-colors shadow-lg btn-press"
+      title={paused ? "Resume agent" : "Pause agent"}
+    >
+      {paused ? (
+        <Play className="w-3.5 h-3.5 text-white fill-white" />
+      ) : (
+        <Pause className="w-3.5 h-3.5 text-white fill-white" />
+      )}
+    </button>
+  );
+}
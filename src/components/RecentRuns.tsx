@@ -0,0 +1,133 @@
+import { useEffect, useState } from "react";
+import { History, Repeat, RotateCw, Trash2 } from "lucide-react";
+import { clsx } from "clsx";
+import { commands } from "../lib/commands";
+import { shrinkToTaskbar } from "../lib/windowManager";
+import type { RunMeta, RunStatus } from "../lib/types";
+import { useAgentStore } from "../stores/agentStore";
+import { useUIStore } from "../stores/uiStore";
+
+const MAX_RUNS = 5;
+
+const statusLabel: Record<RunStatus, string> = {
+  running: "Running",
+  finished: "Finished",
+  failed: "Failed",
+  cancelled: "Stopped",
+  limit_reached: "Limit reached",
+  stopped: "Stopped",
+  interrupted: "Interrupted",
+};
+
+function outcome(run: RunMeta): string {
+  if (run.status === "finished" && run.result) {
+    return run.result.status === "success" ? "Done" : run.result.status;
+  }
+  return statusLabel[run.status];
+}
+
+export function RecentRuns() {
+  const [runs, setRuns] = useState<RunMeta[]>([]);
+  const setAgentTask = useAgentStore((s) => s.setTask);
+  const { setMode, addToast } = useUIStore();
+
+  const refresh = () =>
+    commands
+      .listRuns()
+      .then((all) => setRuns(all.slice(0, MAX_RUNS)))
+      .catch((e) => console.error("Failed to list runs:", e));
+
+  useEffect(() => {
+    refresh();
+  }, []);
+
+  const handleResume = async (run: RunMeta) => {
+    try {
+      setAgentTask(run.task);
+      await commands.resumeRun(run.id);
+      await shrinkToTaskbar();
+      setMode("taskbar");
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
+  const handleReplay = async (run: RunMeta) => {
+    try {
+      setAgentTask(run.task);
+      await commands.replayRun(run.id);
+      await shrinkToTaskbar();
+      setMode("taskbar");
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
+  const handleDelete = async (run: RunMeta) => {
+    try {
+      await commands.deleteRun(run.id);
+      refresh();
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
+  if (runs.length === 0) return null;
+
+  return (
+    <div className="mt-10 w-full max-w-lg animate-slide-up">
+      <p className="flex items-center gap-1.5 text-zinc-500 text-xs font-medium mb-2">
+        <History className="w-3.5 h-3.5" />
+        Recent runs
+      </p>
+      <div className="flex flex-col gap-1">
+        {runs.map((run) => (
+          <div
+            key={run.id}
+            className="flex items-center gap-2 px-3 py-2 bg-zinc-900/50 border border-zinc-800/50 rounded-lg"
+          >
+            <p className="flex-1 min-w-0 text-xs text-zinc-300 truncate" title={run.task}>
+              {run.task}
+            </p>
+            <span
+              className={clsx(
+                "text-[10px] shrink-0",
+                run.status === "interrupted" || run.status === "failed"
+                  ? "text-amber-400"
+                  : "text-zinc-500",
+              )}
+              title={run.error ?? run.result?.summary ?? undefined}
+            >
+              {outcome(run)}
+            </span>
+            {run.status !== "running" && (
+              <button
+                onClick={() => handleResume(run)}
+                className="p-1 rounded-md text-zinc-500 hover:text-zinc-200 hover:bg-zinc-800 transition-colors"
+                title="Resume run"
+              >
+                <RotateCw className="w-3.5 h-3.5" />
+              </button>
+            )}
+            {run.status !== "running" && (
+              <button
+                onClick={() => handleReplay(run)}
+                className="p-1 rounded-md text-zinc-500 hover:text-zinc-200 hover:bg-zinc-800 transition-colors"
+                title="Replay recorded actions"
+              >
+                <Repeat className="w-3.5 h-3.5" />
+              </button>
+            )}
+            <button
+              onClick={() => handleDelete(run)}
+              className="p-1 rounded-md text-zinc-500 hover:text-red-400 hover:bg-zinc-800 transition-colors"
+              title="Delete run"
+            >
+              <Trash2 className="w-3.5 h-3.5" />
+            </button>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
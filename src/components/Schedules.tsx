@@ -0,0 +1,223 @@
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { CalendarClock, Pause, Play, Plus, Trash2 } from "lucide-react";
+import { clsx } from "clsx";
+import { commands } from "../lib/commands";
+import { EVENTS } from "../lib/events";
+import type { MissedRunPolicy, Schedule, WhenBusy } from "../lib/types";
+import { useUIStore } from "../stores/uiStore";
+
+const selectClass =
+  "bg-zinc-950 border border-zinc-800 rounded-lg px-2 py-1.5 text-xs text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700";
+const inputClass =
+  "w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-1.5 text-xs text-white placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700";
+
+function describe(schedule: Schedule): string {
+  if (schedule.paused) return "Paused";
+  if (!schedule.next_run) return schedule.last_outcome ?? "Done";
+  return `Next ${new Date(schedule.next_run).toLocaleString([], {
+    weekday: "short",
+    month: "short",
+    day: "numeric",
+    hour: "2-digit",
+    minute: "2-digit",
+  })}`;
+}
+
+/** Scheduled and recurring tasks. New schedules default to the task typed on the start screen. */
+export function Schedules({ task }: { task: string }) {
+  const [schedules, setSchedules] = useState<Schedule[]>([]);
+  const [creating, setCreating] = useState(false);
+  const [name, setName] = useState("");
+  const [kind, setKind] = useState<"cron" | "once">("cron");
+  const [expression, setExpression] = useState("0 9 * * 1-5");
+  const [at, setAt] = useState("");
+  const [whenBusy, setWhenBusy] = useState<WhenBusy>("queue");
+  const [missedRun, setMissedRun] = useState<MissedRunPolicy>("skip");
+  const addToast = useUIStore((s) => s.addToast);
+
+  useEffect(() => {
+    commands
+      .listSchedules()
+      .then(setSchedules)
+      .catch((e) => console.error("Failed to list schedules:", e));
+
+    const unlisten = listen<Schedule[]>(EVENTS.SCHEDULES_UPDATED, (e) =>
+      setSchedules(e.payload),
+    );
+    return () => {
+      unlisten.then((u) => u());
+    };
+  }, []);
+
+  const handleCreate = async () => {
+    try {
+      await commands.createSchedule({
+        name,
+        task,
+        timing:
+          kind === "cron"
+            ? { type: "cron", expression }
+            : { type: "once", at: new Date(at).toISOString() },
+        when_busy: whenBusy,
+        missed_run: missedRun,
+      });
+      setCreating(false);
+      setName("");
+      addToast("Schedule created", "success");
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
+  const handleToggle = async (schedule: Schedule) => {
+    try {
+      if (schedule.paused) {
+        await commands.resumeSchedule(schedule.id);
+      } else {
+        await commands.pauseSchedule(schedule.id);
+      }
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
+  const handleDelete = async (schedule: Schedule) => {
+    try {
+      await commands.deleteSchedule(schedule.id);
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
+  return (
+    <div className="mt-10 w-full max-w-lg animate-slide-up">
+      <div className="flex items-center mb-2">
+        <p className="flex-1 flex items-center gap-1.5 text-zinc-500 text-xs font-medium">
+          <CalendarClock className="w-3.5 h-3.5" />
+          Schedules
+        </p>
+        <button
+          onClick={() => setCreating(!creating)}
+          disabled={!task.trim()}
+          className="flex items-center gap-1 px-2 py-0.5 rounded-md text-[11px] text-zinc-400 hover:text-zinc-200 hover:bg-zinc-800 transition-colors disabled:opacity-40 disabled:pointer-events-none"
+          title={task.trim() ? "Schedule the task above" : "Type a task first"}
+        >
+          <Plus className="w-3 h-3" />
+          Schedule task
+        </button>
+      </div>
+
+      {creating && (
+        <div className="flex flex-col gap-2 p-3 mb-2 bg-zinc-900/50 border border-zinc-800/50 rounded-lg">
+          <input
+            type="text"
+            value={name}
+            onChange={(e) => setName(e.target.value)}
+            placeholder="Name (optional)"
+            className={inputClass}
+          />
+          <div className="flex items-center gap-2">
+            <select
+              value={kind}
+              onChange={(e) => setKind(e.target.value as "cron" | "once")}
+              className={selectClass}
+            >
+              <option value="cron">Repeat (cron)</option>
+              <option value="once">Once</option>
+            </select>
+            {kind === "cron" ? (
+              <input
+                type="text"
+                value={expression}
+                onChange={(e) => setExpression(e.target.value)}
+                placeholder="min hour day month weekday"
+                className={clsx(inputClass, "font-mono")}
+              />
+            ) : (
+              <input
+                type="datetime-local"
+                value={at}
+                onChange={(e) => setAt(e.target.value)}
+                className={inputClass}
+              />
+            )}
+          </div>
+          <div className="flex items-center gap-2">
+            <select
+              value={whenBusy}
+              onChange={(e) => setWhenBusy(e.target.value as WhenBusy)}
+              className={clsx(selectClass, "flex-1")}
+              title="If the agent is busy when the schedule fires"
+            >
+              <option value="queue">If busy: queue it</option>
+              <option value="skip">If busy: skip it</option>
+            </select>
+            <select
+              value={missedRun}
+              onChange={(e) => setMissedRun(e.target.value as MissedRunPolicy)}
+              className={clsx(selectClass, "flex-1")}
+              title="Runs that came due while the app was closed or the computer slept"
+            >
+              <option value="skip">Missed runs: skip</option>
+              <option value="run_once">Missed runs: run once</option>
+            </select>
+          </div>
+          <button
+            onClick={handleCreate}
+            disabled={kind === "cron" ? !expression.trim() : !at}
+            className="self-end px-3 py-1 rounded-md text-xs font-medium bg-zinc-50 text-zinc-900 hover:bg-zinc-200 disabled:bg-zinc-700 disabled:text-zinc-500 transition-colors"
+          >
+            Create
+          </button>
+        </div>
+      )}
+
+      <div className="flex flex-col gap-1">
+        {schedules.map((schedule) => (
+          <div
+            key={schedule.id}
+            className="flex items-center gap-2 px-3 py-2 bg-zinc-900/50 border border-zinc-800/50 rounded-lg"
+          >
+            <p className="flex-1 min-w-0 text-xs text-zinc-300 truncate" title={schedule.task}>
+              {schedule.name}
+            </p>
+            <span
+              className={clsx(
+                "text-[10px] shrink-0",
+                schedule.last_outcome?.startsWith("failed")
+                  ? "text-amber-400"
+                  : "text-zinc-500",
+              )}
+              title={
+                schedule.timing.type === "cron"
+                  ? `${schedule.timing.expression} · last: ${schedule.last_outcome ?? "never run"}`
+                  : `last: ${schedule.last_outcome ?? "never run"}`
+              }
+            >
+              {describe(schedule)}
+            </span>
+            <button
+              onClick={() => handleToggle(schedule)}
+              className="p-1 rounded-md text-zinc-500 hover:text-zinc-200 hover:bg-zinc-800 transition-colors"
+              title={schedule.paused ? "Resume schedule" : "Pause schedule"}
+            >
+              {schedule.paused ? (
+                <Play className="w-3.5 h-3.5" />
+              ) : (
+                <Pause className="w-3.5 h-3.5" />
+              )}
+            </button>
+            <button
+              onClick={() => handleDelete(schedule)}
+              className="p-1 rounded-md text-zinc-500 hover:text-red-400 hover:bg-zinc-800 transition-colors"
+              title="Delete schedule"
+            >
+              <Trash2 className="w-3.5 h-3.5" />
+            </button>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
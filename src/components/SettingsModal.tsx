@@ -1,34 +1,161 @@
 import { useState, useEffect, useMemo } from "react";
-import { X, Eye, EyeOff, RotateCcw, Search, Loader2 } from "lucide-react";
+import {
+  X,
+  Eye,
+  EyeOff,
+  RotateCcw,
+  Search,
+  Loader2,
+  Plus,
+  Trash2,
+} from "lucide-react";
 import { useSettingsStore } from "../stores/settingsStore";
-import { PROVIDERS, MODELS, fetchModelsDetailed } from "../lib/types";
+import { commands } from "../lib/commands";
+import {
+  ACTION_KINDS,
+  PROVIDERS,
+  MODELS,
+  fetchModelsDetailed,
+  type ApprovalPolicy,
+  type FallbackProvider,
+  type ModelOption,
+  type OllamaOptions,
+  type PromptOverride,
+  type ProviderProfile,
+  type TokenLimitField,
+} from "../lib/types";
 
 export function SettingsModal() {
   const { settings, updateSettings, saveSettings, setShowModal, resetWizard } =
     useSettingsStore();
   const [showKey, setShowKey] = useState(false);
-  const [dynamicModels, setDynamicModels] = useState(
+  const [dynamicModels, setDynamicModels] = useState<ModelOption[]>(
     MODELS[settings.provider] ?? []
   );
   const [loadingModels, setLoadingModels] = useState(false);
   const [modelSearch, setModelSearch] = useState("");
   const [modelsError, setModelsError] = useState(false);
+  const [promptPreview, setPromptPreview] = useState<string | null>(null);
+
+  const customProvider = settings.custom_providers.find(
+    (p) => p.id === settings.provider
+  );
+  const providerOptions = [
+    ...PROVIDERS,
+    ...settings.custom_providers.map((p) => ({ id: p.id, label: p.label })),
+  ];
+
+  const updateOllamaOptions = (partial: Partial<OllamaOptions>) => {
+    updateSettings({
+      ollama_options: { ...settings.ollama_options, ...partial },
+    });
+  };
+
+  const optionalNumber = (value: string) =>
+    value.trim() === "" ? null : Number(value);
+
+  const selectedModel = dynamicModels.find((m) => m.id === settings.model);
+  const missingCapabilities = [
+    selectedModel?.vision === false && "vision",
+    selectedModel?.tool_calling === false && "tool calling",
+  ].filter(Boolean);
+
+  const updateFallback = (index: number, partial: Partial<FallbackProvider>) => {
+    updateSettings({
+      fallback_providers: settings.fallback_providers.map((f, i) =>
+        i === index ? { ...f, ...partial } : f
+      ),
+    });
+  };
+
+  const providerPrompt: PromptOverride = settings.provider_prompts[
+    settings.provider
+  ] ?? { template: "", instructions: "" };
+
+  const updateProviderPrompt = (partial: Partial<PromptOverride>) => {
+    const next = { ...providerPrompt, ...partial };
+    const others = Object.fromEntries(
+      Object.entries(settings.provider_prompts).filter(
+        ([id]) => id !== settings.provider
+      )
+    );
+    updateSettings({
+      provider_prompts:
+        next.template.trim() || next.instructions.trim()
+          ? { ...others, [settings.provider]: next }
+          : others,
+    });
+  };
+
+  const previewPrompt = async () => {
+    try {
+      setPromptPreview(await commands.previewSystemPrompt(settings));
+    } catch (e) {
+      setPromptPreview(`Preview failed: ${e}`);
+    }
+  };
+
+  const updateCustomProvider = (partial: Partial<ProviderProfile>) => {
+    updateSettings({
+      custom_providers: settings.custom_providers.map((p) =>
+        p.id === settings.provider ? { ...p, ...partial } : p
+      ),
+    });
+  };
+
+  const addCustomProvider = () => {
+    const id = `custom-${crypto.randomUUID().slice(0, 8)}`;
+    updateSettings({
+      custom_providers: [
+        ...settings.custom_providers,
+        {
+          id,
+          label: "Custom",
+          base_url: "http://127.0.0.1:1234/v1",
+          api_key: "",
+          auth: { type: "bearer" },
+          extra_headers: {},
+          token_limit_field: "max_tokens",
+        },
+      ],
+      provider: id,
+      model: "",
+    });
+  };
+
+  const removeCustomProvider = () => {
+    updateSettings({
+      custom_providers: settings.custom_providers.filter(
+        (p) => p.id !== settings.provider
+      ),
+      provider: "anthropic",
+      model: MODELS.anthropic[0].id,
+    });
+  };
 
   // Fetch models dynamically when provider or key changes
   useEffect(() => {
-    const key =
-      settings.provider === "anthropic"
+    const key = customProvider
+      ? customProvider.api_key
+      : settings.provider === "anthropic"
         ? settings.anthropic_api_key
         : settings.provider === "openrouter"
           ? settings.openrouter_api_key
-          : settings.provider === "ollama"
-            ? settings.ollama_api_key
-            : settings.openai_api_key;
+          : settings.provider === "gemini"
+            ? settings.gemini_api_key
+            : settings.provider === "ollama"
+              ? settings.ollama_api_key
+              : settings.openai_api_key;
     setDynamicModels([]);
     setModelSearch("");
     setModelsError(false);
     setLoadingModels(true);
-    fetchModelsDetailed(settings.provider, key, settings.ollama_base_url)
+    fetchModelsDetailed(
+      settings.provider,
+      key,
+      settings.ollama_base_url,
+      settings.custom_providers
+    )
       .then((result) => {
         setDynamicModels(result.models);
         setModelsError(result.hadError);
@@ -40,7 +167,10 @@ export function SettingsModal() {
     settings.openai_api_key,
     settings.openrouter_api_key,
     settings.ollama_api_key,
+    settings.gemini_api_key,
     settings.ollama_base_url,
+    customProvider?.base_url,
+    customProvider?.api_key,
   ]);
 
   const handleClose = async () => {
@@ -49,27 +179,35 @@ export function SettingsModal() {
   };
 
   const getApiKey = () => {
+    if (customProvider) return customProvider.api_key;
     if (settings.provider === "anthropic") return settings.anthropic_api_key;
     if (settings.provider === "openrouter") return settings.openrouter_api_key;
+    if (settings.provider === "gemini") return settings.gemini_api_key;
     if (settings.provider === "ollama") return settings.ollama_api_key;
     return settings.openai_api_key;
   };
 
   const setApiKey = (value: string) => {
-    if (settings.provider === "anthropic")
+    if (customProvider) updateCustomProvider({ api_key: value });
+    else if (settings.provider === "anthropic")
       updateSettings({ anthropic_api_key: value });
     else if (settings.provider === "openrouter")
       updateSettings({ openrouter_api_key: value });
+    else if (settings.provider === "gemini")
+      updateSettings({ gemini_api_key: value });
     else if (settings.provider === "ollama")
       updateSettings({ ollama_api_key: value });
     else updateSettings({ openai_api_key: value });
   };
 
-  const keyPlaceholder =
-    settings.provider === "anthropic"
+  const keyPlaceholder = customProvider
+    ? "Optional"
+    : settings.provider === "anthropic"
       ? "sk-ant-..."
       : settings.provider === "openrouter"
         ? "sk-or-..."
+        : settings.provider === "gemini"
+          ? "AIza..."
         : settings.provider === "ollama"
           ? "Optional (leave empty for local Ollama)"
         : "sk-...";
@@ -103,8 +241,8 @@ export function SettingsModal() {
             <label className="block text-xs font-medium text-zinc-400 mb-1.5">
               Provider
             </label>
-            <div className="flex bg-zinc-950 rounded-lg p-0.5 border border-zinc-800">
-              {PROVIDERS.map((p) => (
+            <div className="flex flex-wrap bg-zinc-950 rounded-lg p-0.5 border border-zinc-800">
+              {providerOptions.map((p) => (
                 <button
                   key={p.id}
                   onClick={() => {
@@ -123,14 +261,71 @@ export function SettingsModal() {
                   {p.label}
                 </button>
               ))}
+              <button
+                onClick={addCustomProvider}
+                title="Add OpenAI-compatible endpoint"
+                className="px-2 py-1.5 rounded-md text-zinc-500 hover:text-zinc-300"
+              >
+                <Plus className="w-3.5 h-3.5" />
+              </button>
             </div>
           </div>
 
+          {customProvider && (
+            <div className="space-y-2">
+              <label className="block text-xs font-medium text-zinc-400">
+                OpenAI-compatible endpoint
+              </label>
+              <input
+                type="text"
+                value={customProvider.label}
+                onChange={(e) => updateCustomProvider({ label: e.target.value })}
+                placeholder="Name"
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-white placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+              />
+              <input
+                type="text"
+                value={customProvider.base_url}
+                onChange={(e) =>
+                  updateCustomProvider({ base_url: e.target.value })
+                }
+                placeholder="http://127.0.0.1:1234/v1"
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-white placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+              />
+              <div className="flex gap-2">
+                <select
+                  value={customProvider.token_limit_field}
+                  onChange={(e) =>
+                    updateCustomProvider({
+                      token_limit_field: e.target.value as TokenLimitField,
+                    })
+                  }
+                  className="flex-1 bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-xs text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                >
+                  <option value="max_tokens">max_tokens</option>
+                  <option value="max_completion_tokens">
+                    max_completion_tokens
+                  </option>
+                  <option value="omit">No token limit</option>
+                </select>
+                <button
+                  onClick={removeCustomProvider}
+                  className="px-3 rounded-lg border border-zinc-800 text-zinc-500 hover:text-red-400"
+                >
+                  <Trash2 className="w-3.5 h-3.5" />
+                </button>
+              </div>
+            </div>
+          )}
+
           {/* API Key */}
           <div>
             <label className="block text-xs font-medium text-zinc-400 mb-1.5">
-              {PROVIDERS.find((p) => p.id === settings.provider)?.label} API Key
-              {settings.provider === "ollama" ? " (Optional)" : ""}
+              {providerOptions.find((p) => p.id === settings.provider)?.label}{" "}
+              API Key
+              {settings.provider === "ollama" || customProvider
+                ? " (Optional)"
+                : ""}
             </label>
             <div className="relative">
               <input
@@ -168,6 +363,95 @@ export function SettingsModal() {
                 placeholder="http://127.0.0.1:11434"
                 className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-white placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
               />
+              <label className="flex items-center gap-2 mt-2 text-xs text-zinc-400">
+                <input
+                  type="checkbox"
+                  checked={settings.ollama_options.native_api}
+                  onChange={(e) =>
+                    updateOllamaOptions({ native_api: e.target.checked })
+                  }
+                />
+                Use native /api/chat endpoint
+              </label>
+              {settings.ollama_options.native_api && (
+                <div className="mt-2 space-y-2">
+                  <div className="grid grid-cols-3 gap-2">
+                    <input
+                      type="number"
+                      min={0}
+                      value={settings.ollama_options.num_ctx ?? ""}
+                      onChange={(e) =>
+                        updateOllamaOptions({
+                          num_ctx: optionalNumber(e.target.value),
+                        })
+                      }
+                      className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                      placeholder="num_ctx"
+                    />
+                    <input
+                      type="number"
+                      min={0}
+                      step={0.1}
+                      value={settings.ollama_options.temperature ?? ""}
+                      onChange={(e) =>
+                        updateOllamaOptions({
+                          temperature: optionalNumber(e.target.value),
+                        })
+                      }
+                      className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                      placeholder="Temperature"
+                    />
+                    <input
+                      type="number"
+                      min={-1}
+                      value={settings.ollama_options.num_predict ?? ""}
+                      onChange={(e) =>
+                        updateOllamaOptions({
+                          num_predict: optionalNumber(e.target.value),
+                        })
+                      }
+                      className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                      placeholder="num_predict"
+                    />
+                  </div>
+                  <input
+                    type="text"
+                    value={settings.ollama_options.keep_alive}
+                    onChange={(e) =>
+                      updateOllamaOptions({ keep_alive: e.target.value })
+                    }
+                    className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                    placeholder="Keep alive (e.g. 10m, -1 to keep loaded)"
+                  />
+                  <label className="flex items-center gap-2 text-xs text-zinc-400">
+                    <input
+                      type="checkbox"
+                      checked={settings.ollama_options.json_format}
+                      onChange={(e) =>
+                        updateOllamaOptions({ json_format: e.target.checked })
+                      }
+                    />
+                    JSON mode (format: json)
+                  </label>
+                </div>
+              )}
+            </div>
+          )}
+
+          {settings.provider === "gemini" && (
+            <div>
+              <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+                Gemini API URL
+              </label>
+              <input
+                type="text"
+                value={settings.gemini_base_url}
+                onChange={(e) =>
+                  updateSettings({ gemini_base_url: e.target.value })
+                }
+                placeholder="https://generativelanguage.googleapis.com/v1beta"
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-white placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+              />
             </div>
           )}
 
@@ -220,10 +504,21 @@ export function SettingsModal() {
                         {m.id}
                       </span>
                     )}
+                    {m.context_length != null && (
+                      <span className="block truncate text-zinc-600 text-[10px] mt-0.5">
+                        {m.context_length.toLocaleString()} token context
+                      </span>
+                    )}
                   </button>
                 ))
               )}
             </div>
+            {missingCapabilities.length > 0 && (
+              <p className="text-[11px] text-amber-400 mt-1.5">
+                This model does not support {missingCapabilities.join(" or ")}
+                , so the agent may not be able to see the screen or act on it.
+              </p>
+            )}
           </div>
 
           {/* Display Resolution */}
@@ -253,6 +548,515 @@ export function SettingsModal() {
             </div>
           </div>
 
+          {/* Fallback chain */}
+          <div>
+            <div className="flex items-center justify-between mb-1.5">
+              <label className="block text-xs font-medium text-zinc-400">
+                Fallback providers (tried in order)
+              </label>
+              <button
+                onClick={() =>
+                  updateSettings({
+                    fallback_providers: [
+                      ...settings.fallback_providers,
+                      {
+                        provider: "ollama",
+                        model: MODELS.ollama[0].id,
+                      },
+                    ],
+                  })
+                }
+                title="Add fallback"
+                className="p-1 rounded-md text-zinc-500 hover:text-zinc-300"
+              >
+                <Plus className="w-3.5 h-3.5" />
+              </button>
+            </div>
+            <div className="space-y-2">
+              {settings.fallback_providers.map((f, i) => (
+                <div key={i} className="flex gap-2">
+                  <select
+                    value={f.provider}
+                    onChange={(e) =>
+                      updateFallback(i, {
+                        provider: e.target.value,
+                        model: (MODELS[e.target.value] ?? [])[0]?.id ?? "",
+                      })
+                    }
+                    className="w-32 bg-zinc-950 border border-zinc-800 rounded-lg px-2 py-2 text-xs text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                  >
+                    {providerOptions.map((p) => (
+                      <option key={p.id} value={p.id}>
+                        {p.label}
+                      </option>
+                    ))}
+                  </select>
+                  <input
+                    type="text"
+                    value={f.model}
+                    onChange={(e) => updateFallback(i, { model: e.target.value })}
+                    placeholder="Model"
+                    className="flex-1 min-w-0 bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-xs text-white placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                  />
+                  <button
+                    onClick={() =>
+                      updateSettings({
+                        fallback_providers: settings.fallback_providers.filter(
+                          (_, j) => j !== i
+                        ),
+                      })
+                    }
+                    className="px-3 rounded-lg border border-zinc-800 text-zinc-500 hover:text-red-400"
+                  >
+                    <Trash2 className="w-3.5 h-3.5" />
+                  </button>
+                </div>
+              ))}
+            </div>
+          </div>
+
+          {/* Reasoning */}
+          {(settings.provider === "anthropic" ||
+            settings.provider === "gemini") && (
+            <div>
+              <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+                Thinking budget (tokens, 0 = off)
+              </label>
+              <input
+                type="number"
+                min={0}
+                step={1024}
+                value={settings.thinking_budget_tokens}
+                onChange={(e) =>
+                  updateSettings({
+                    thinking_budget_tokens: Number(e.target.value),
+                  })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+              />
+            </div>
+          )}
+          {settings.provider === "openai" && (
+            <div>
+              <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+                Reasoning effort (o-series models)
+              </label>
+              <select
+                value={settings.reasoning_effort}
+                onChange={(e) =>
+                  updateSettings({
+                    reasoning_effort: e.target
+                      .value as typeof settings.reasoning_effort,
+                  })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+              >
+                <option value="">Default</option>
+                <option value="low">Low</option>
+                <option value="medium">Medium</option>
+                <option value="high">High</option>
+              </select>
+            </div>
+          )}
+
+          {/* Provider retries */}
+          <div>
+            <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+              Retries (max attempts / max delay in seconds)
+            </label>
+            <div className="grid grid-cols-2 gap-2">
+              <input
+                type="number"
+                min={1}
+                value={settings.retry_max_attempts}
+                onChange={(e) =>
+                  updateSettings({ retry_max_attempts: Number(e.target.value) })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Attempts"
+              />
+              <input
+                type="number"
+                min={1}
+                value={settings.retry_max_delay_secs}
+                onChange={(e) =>
+                  updateSettings({
+                    retry_max_delay_secs: Number(e.target.value),
+                  })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Max delay"
+              />
+            </div>
+          </div>
+
+          {/* Per-task budget */}
+          <div>
+            <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+              Task budget (max tokens / max USD, 0 = unlimited)
+            </label>
+            <div className="grid grid-cols-2 gap-2">
+              <input
+                type="number"
+                min={0}
+                value={settings.max_tokens_per_task}
+                onChange={(e) =>
+                  updateSettings({ max_tokens_per_task: Number(e.target.value) })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Tokens"
+              />
+              <input
+                type="number"
+                min={0}
+                step={0.01}
+                value={settings.max_cost_per_task_usd}
+                onChange={(e) =>
+                  updateSettings({
+                    max_cost_per_task_usd: Number(e.target.value),
+                  })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="USD"
+              />
+            </div>
+          </div>
+
+          {/* Run limits */}
+          <div>
+            <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+              Run limits (max iterations / max minutes, 0 = unlimited)
+            </label>
+            <div className="grid grid-cols-2 gap-2">
+              <input
+                type="number"
+                min={0}
+                value={settings.max_iterations}
+                onChange={(e) =>
+                  updateSettings({ max_iterations: Number(e.target.value) })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Iterations"
+              />
+              <input
+                type="number"
+                min={0}
+                value={Math.round(settings.max_run_duration_secs / 60)}
+                onChange={(e) =>
+                  updateSettings({
+                    max_run_duration_secs: Number(e.target.value) * 60,
+                  })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Minutes"
+              />
+            </div>
+            <div className="grid grid-cols-2 gap-2 mt-2">
+              {ACTION_KINDS.map((kind) => (
+                <label
+                  key={kind}
+                  className="flex items-center justify-between gap-2 text-[11px] text-zinc-500"
+                >
+                  {kind}
+                  <input
+                    type="number"
+                    min={0}
+                    value={settings.max_actions[kind] ?? 0}
+                    onChange={(e) =>
+                      updateSettings({
+                        max_actions: {
+                          ...settings.max_actions,
+                          [kind]: Number(e.target.value),
+                        },
+                      })
+                    }
+                    className="w-16 bg-zinc-950 border border-zinc-800 rounded-lg px-2 py-1 text-xs text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                  />
+                </label>
+              ))}
+            </div>
+          </div>
+
+          {/* Stuck-loop detection */}
+          <div>
+            <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+              Stuck detection (repeats / actions without change / nudges before
+              pausing, 0 = off)
+            </label>
+            <div className="grid grid-cols-3 gap-2">
+              <input
+                type="number"
+                min={0}
+                value={settings.stuck_repeat_threshold}
+                onChange={(e) =>
+                  updateSettings({ stuck_repeat_threshold: Number(e.target.value) })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Repeats"
+              />
+              <input
+                type="number"
+                min={0}
+                value={settings.stuck_unchanged_threshold}
+                onChange={(e) =>
+                  updateSettings({
+                    stuck_unchanged_threshold: Number(e.target.value),
+                  })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Unchanged"
+              />
+              <input
+                type="number"
+                min={0}
+                value={settings.stuck_max_nudges}
+                onChange={(e) =>
+                  updateSettings({ stuck_max_nudges: Number(e.target.value) })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Nudges"
+              />
+            </div>
+          </div>
+
+          {/* Completion */}
+          <div>
+            <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+              Reminders to call task_complete before a silent stop ends the run
+            </label>
+            <input
+              type="number"
+              min={0}
+              value={settings.completion_reminders}
+              onChange={(e) =>
+                updateSettings({ completion_reminders: Number(e.target.value) })
+              }
+              className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+            />
+          </div>
+
+          {/* Context compaction */}
+          <div>
+            <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+              Compaction (summarize history above tokens, 0 = off / recent
+              messages kept as-is)
+            </label>
+            <div className="grid grid-cols-2 gap-2">
+              <input
+                type="number"
+                min={0}
+                step={10000}
+                value={settings.compaction_threshold_tokens}
+                onChange={(e) =>
+                  updateSettings({
+                    compaction_threshold_tokens: Number(e.target.value),
+                  })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Tokens"
+              />
+              <input
+                type="number"
+                min={2}
+                value={settings.compaction_keep_recent}
+                onChange={(e) =>
+                  updateSettings({
+                    compaction_keep_recent: Math.max(2, Number(e.target.value)),
+                  })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Recent messages"
+              />
+            </div>
+          </div>
+
+          {/* Screenshot history */}
+          <div>
+            <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+              Screenshot history (full size / thumbnails / thumbnail width px)
+            </label>
+            <div className="grid grid-cols-3 gap-2">
+              <input
+                type="number"
+                min={0}
+                value={settings.screenshot_history_full}
+                onChange={(e) =>
+                  updateSettings({ screenshot_history_full: Number(e.target.value) })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Full"
+              />
+              <input
+                type="number"
+                min={0}
+                value={settings.screenshot_history_thumbnails}
+                onChange={(e) =>
+                  updateSettings({
+                    screenshot_history_thumbnails: Number(e.target.value),
+                  })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Thumbnails"
+              />
+              <input
+                type="number"
+                min={64}
+                step={32}
+                value={settings.screenshot_thumbnail_width}
+                onChange={(e) =>
+                  updateSettings({
+                    screenshot_thumbnail_width: Math.max(64, Number(e.target.value)),
+                  })
+                }
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Width"
+              />
+            </div>
+          </div>
+
+          {/* System prompt */}
+          <div>
+            <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+              Custom instructions (added to every system prompt)
+            </label>
+            <textarea
+              value={settings.custom_instructions}
+              onChange={(e) =>
+                updateSettings({ custom_instructions: e.target.value })
+              }
+              rows={3}
+              className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+              placeholder="e.g. Use the Work browser profile. Never close unsaved documents."
+            />
+            <label className="block text-xs font-medium text-zinc-400 mt-3 mb-1.5">
+              System prompt template (variables: {"{{os}} {{date}} {{screen_size}} {{username}} {{shell}} {{custom_instructions}}"})
+            </label>
+            <textarea
+              value={settings.system_prompt_template}
+              onChange={(e) =>
+                updateSettings({ system_prompt_template: e.target.value })
+              }
+              rows={4}
+              className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-xs font-mono text-zinc-300 placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+              placeholder="Leave empty for the built-in prompt"
+            />
+            <label className="block text-xs font-medium text-zinc-400 mt-3 mb-1.5">
+              Overrides for{" "}
+              {providerOptions.find((p) => p.id === settings.provider)?.label ??
+                settings.provider}{" "}
+              (template replaces the one above, instructions are added)
+            </label>
+            <div className="space-y-2">
+              <textarea
+                value={providerPrompt.template}
+                onChange={(e) =>
+                  updateProviderPrompt({ template: e.target.value })
+                }
+                rows={2}
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-xs font-mono text-zinc-300 placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Template for this provider (optional)"
+              />
+              <textarea
+                value={providerPrompt.instructions}
+                onChange={(e) =>
+                  updateProviderPrompt({ instructions: e.target.value })
+                }
+                rows={2}
+                className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 placeholder:text-zinc-600 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                placeholder="Extra instructions for this provider (optional)"
+              />
+            </div>
+            <button
+              type="button"
+              onClick={() =>
+                promptPreview === null ? previewPrompt() : setPromptPreview(null)
+              }
+              className="mt-2 flex items-center gap-1.5 text-xs text-zinc-400 hover:text-zinc-200 transition-colors"
+            >
+              <Eye className="w-3.5 h-3.5" />
+              {promptPreview === null ? "Preview system prompt" : "Hide preview"}
+            </button>
+            {promptPreview !== null && (
+              <pre className="mt-2 max-h-64 overflow-y-auto whitespace-pre-wrap bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-[11px] text-zinc-400">
+                {promptPreview}
+              </pre>
+            )}
+          </div>
+
+          {/* Long-term memory */}
+          <div>
+            <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+              Long-term memory (relevant memories added to each new task)
+            </label>
+            <div className="flex items-center gap-3">
+              <label className="flex items-center gap-2 text-xs text-zinc-400">
+                <input
+                  type="checkbox"
+                  checked={settings.memory_enabled}
+                  onChange={(e) =>
+                    updateSettings({ memory_enabled: e.target.checked })
+                  }
+                />
+                Enabled
+              </label>
+              <input
+                type="number"
+                min={0}
+                value={settings.memory_inject_count}
+                disabled={!settings.memory_enabled}
+                onChange={(e) =>
+                  updateSettings({ memory_inject_count: Number(e.target.value) })
+                }
+                className="flex-1 bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700 disabled:opacity-40"
+                placeholder="Memories per task"
+              />
+            </div>
+          </div>
+
+          {/* Approvals */}
+          <div>
+            <label className="block text-xs font-medium text-zinc-400 mb-1.5">
+              Action approvals (auto-reject after seconds)
+            </label>
+            <input
+              type="number"
+              min={1}
+              value={settings.approval_timeout_secs}
+              onChange={(e) =>
+                updateSettings({
+                  approval_timeout_secs: Math.max(1, Number(e.target.value)),
+                })
+              }
+              className="w-full bg-zinc-950 border border-zinc-800 rounded-lg px-3 py-2 text-sm text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+            />
+            <div className="grid grid-cols-2 gap-2 mt-2">
+              {ACTION_KINDS.map((kind) => (
+                <label
+                  key={kind}
+                  className="flex items-center justify-between gap-2 text-[11px] text-zinc-500"
+                >
+                  {kind}
+                  <select
+                    value={settings.approval_policies[kind] ?? "auto"}
+                    onChange={(e) =>
+                      updateSettings({
+                        approval_policies: {
+                          ...settings.approval_policies,
+                          [kind]: e.target.value as ApprovalPolicy,
+                        },
+                      })
+                    }
+                    className="bg-zinc-950 border border-zinc-800 rounded-lg px-2 py-1 text-xs text-zinc-300 focus:outline-none focus:ring-1 focus:ring-zinc-700"
+                  >
+                    <option value="auto">Auto</option>
+                    <option value="ask">Ask</option>
+                    <option value="deny">Deny</option>
+                  </select>
+                </label>
+              ))}
+            </div>
+          </div>
+
           {/* Divider */}
           <div className="border-t border-zinc-800" />
 
@@ -18,6 +18,7 @@ const STEPS = ["Welcome", "Provider", "API Key", "Display", "Ready"] as const;
 const providerDescriptions: Record<string, string> = {
   anthropic: "Claude models with native computer use",
   openai: "GPT-4.1 and o-series with vision",
+  gemini: "Gemini models with vision and function calling",
   openrouter: "Access many models via one API key",
   ollama: "Run local models via Ollama",
 };
@@ -67,6 +68,7 @@ export function SetupWizard({ onComplete }: { onComplete: () => void }) {
   const getApiKeyForProvider = () => {
     if (settings.provider === "anthropic") return settings.anthropic_api_key;
     if (settings.provider === "openrouter") return settings.openrouter_api_key;
+    if (settings.provider === "gemini") return settings.gemini_api_key;
     if (settings.provider === "ollama") return settings.ollama_api_key;
     return settings.openai_api_key;
   };
@@ -76,6 +78,8 @@ export function SetupWizard({ onComplete }: { onComplete: () => void }) {
       updateSettings({ anthropic_api_key: value });
     else if (settings.provider === "openrouter")
       updateSettings({ openrouter_api_key: value });
+    else if (settings.provider === "gemini")
+      updateSettings({ gemini_api_key: value });
     else if (settings.provider === "ollama")
       updateSettings({ ollama_api_key: value });
     else updateSettings({ openai_api_key: value });
@@ -240,6 +244,8 @@ export function SetupWizard({ onComplete }: { onComplete: () => void }) {
                       ? "sk-ant-..."
                       : settings.provider === "openrouter"
                         ? "sk-or-..."
+                        : settings.provider === "gemini"
+                          ? "AIza..."
                         : settings.provider === "ollama"
                           ? "Optional (leave empty for local Ollama)"
                         : "sk-..."
@@ -10,6 +10,7 @@ import {
   Image as ImageIcon,
   Globe,
   FileText,
+  ListPlus,
 } from "lucide-react";
 import { clsx } from "clsx";
 import { commands } from "../lib/commands";
@@ -18,7 +19,11 @@ import { PROVIDERS, MODELS, fetchModelsDetailed } from "../lib/types";
 import { useSettingsStore } from "../stores/settingsStore";
 import { useAgentStore } from "../stores/agentStore";
 import { useUIStore } from "../stores/uiStore";
+import { RecentRuns } from "./RecentRuns";
+import { Schedules } from "./Schedules";
+import { Memories } from "./Memories";
 import { SettingsModal } from "./SettingsModal";
+import { TaskQueue } from "./TaskQueue";
 import { Spinner } from "./Spinner";
 
 export function StartScreen() {
@@ -44,9 +49,11 @@ export function StartScreen() {
         ? settings.anthropic_api_key
         : settings.provider === "openrouter"
           ? settings.openrouter_api_key
-          : settings.provider === "ollama"
-            ? settings.ollama_api_key
-            : settings.openai_api_key;
+          : settings.provider === "gemini"
+            ? settings.gemini_api_key
+            : settings.provider === "ollama"
+              ? settings.ollama_api_key
+              : settings.openai_api_key;
     setDynamicModels([]);
     setModelsError(false);
     setLoadingModels(true);
@@ -62,6 +69,7 @@ export function StartScreen() {
     settings.openai_api_key,
     settings.openrouter_api_key,
     settings.ollama_api_key,
+    settings.gemini_api_key,
     settings.ollama_base_url,
   ]);
 
@@ -90,23 +98,28 @@ export function StartScreen() {
     settings.model ??
     "Select model";
 
-  const handleSubmit = async () => {
-    if (!task.trim()) return;
-
+  const hasApiKey = () => {
     const apiKey =
       settings.provider === "anthropic"
         ? settings.anthropic_api_key
         : settings.provider === "openrouter"
           ? settings.openrouter_api_key
-          : settings.provider === "ollama"
-            ? settings.ollama_api_key
-            : settings.openai_api_key;
+          : settings.provider === "gemini"
+            ? settings.gemini_api_key
+            : settings.provider === "ollama"
+              ? settings.ollama_api_key
+              : settings.openai_api_key;
 
     if (!apiKey && settings.provider !== "ollama") {
       addToast("Please set your API key in settings first.", "error");
       setShowModal(true);
-      return;
+      return false;
     }
+    return true;
+  };
+
+  const handleSubmit = async () => {
+    if (!task.trim() || !hasApiKey()) return;
 
     setLoading(true);
 
@@ -122,6 +135,20 @@ export function StartScreen() {
     }
   };
 
+  // Runs right away when the agent is idle, otherwise after the tasks ahead of it
+  const handleEnqueue = async () => {
+    if (!task.trim() || !hasApiKey()) return;
+
+    try {
+      await saveSettings();
+      await commands.enqueueTask(task.trim());
+      setTask("");
+      addToast("Added to the task queue", "success");
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
   const handleKeyDown = (e: React.KeyboardEvent) => {
     if (e.key === "Enter" && !e.shiftKey) {
       e.preventDefault();
@@ -266,24 +293,52 @@ export function StartScreen() {
         </button>
       </div>
 
-      {/* Go Button */}
-      <button
-        onClick={handleSubmit}
-        disabled={loading || !task.trim()}
-        className={clsx(
-          "mt-6 px-8 py-2.5 rounded-full font-medium text-sm flex items-center gap-2 transition-all btn-press",
-          loading || !task.trim()
-            ? "bg-zinc-700 text-zinc-500 cursor-not-allowed"
-            : "bg-zinc-50 text-zinc-900 hover:bg-zinc-200 shadow-sm",
-        )}
-      >
-        {loading ? (
-          <Spinner size={16} className="text-zinc-400" />
-        ) : (
-          <Play className="w-4 h-4" />
-        )}
-        {loading ? "Starting..." : "Go"}
-      </button>
+      {/* Go / Queue Buttons */}
+      <div className="mt-6 flex items-center gap-2">
+        <button
+          onClick={handleSubmit}
+          disabled={loading || !task.trim()}
+          className={clsx(
+            "px-8 py-2.5 rounded-full font-medium text-sm flex items-center gap-2 transition-all btn-press",
+            loading || !task.trim()
+              ? "bg-zinc-700 text-zinc-500 cursor-not-allowed"
+              : "bg-zinc-50 text-zinc-900 hover:bg-zinc-200 shadow-sm",
+          )}
+        >
+          {loading ? (
+            <Spinner size={16} className="text-zinc-400" />
+          ) : (
+            <Play className="w-4 h-4" />
+          )}
+          {loading ? "Starting..." : "Go"}
+        </button>
+        <button
+          onClick={handleEnqueue}
+          disabled={loading || !task.trim()}
+          className={clsx(
+            "px-4 py-2.5 rounded-full font-medium text-sm flex items-center gap-2 transition-all btn-press border",
+            loading || !task.trim()
+              ? "border-zinc-800 text-zinc-600 cursor-not-allowed"
+              : "border-zinc-700 text-zinc-300 hover:bg-zinc-800 hover:text-white",
+          )}
+          title="Add to the task queue"
+        >
+          <ListPlus className="w-4 h-4" />
+          Queue
+        </button>
+      </div>
+
+      {/* Queued tasks and how recent ones ended */}
+      <TaskQueue />
+
+      {/* Scheduled and recurring tasks */}
+      <Schedules task={task} />
+
+      {/* Recent runs, including ones a crash cut short */}
+      <RecentRuns />
+
+      {/* What the agent remembers across tasks */}
+      <Memories />
 
       {/* Suggested Prompts */}
       <div
@@ -0,0 +1,156 @@
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { ArrowDown, ArrowUp, ListOrdered, Play, X } from "lucide-react";
+import { clsx } from "clsx";
+import { commands } from "../lib/commands";
+import { EVENTS } from "../lib/events";
+import type { QueuedTask, QueuedTaskStatus } from "../lib/types";
+import { useUIStore } from "../stores/uiStore";
+
+const MAX_ENDED = 3;
+
+const statusLabel: Record<QueuedTaskStatus, string> = {
+  queued: "Queued",
+  running: "Running",
+  finished: "Finished",
+  failed: "Failed",
+  cancelled: "Cancelled",
+};
+
+function outcome(task: QueuedTask): string {
+  if (task.status === "finished" && task.result) {
+    return task.result.status === "success" ? "Done" : task.result.status;
+  }
+  return statusLabel[task.status];
+}
+
+export function TaskQueue() {
+  const [tasks, setTasks] = useState<QueuedTask[]>([]);
+  const addToast = useUIStore((s) => s.addToast);
+
+  useEffect(() => {
+    commands
+      .listQueue()
+      .then(setTasks)
+      .catch((e) => console.error("Failed to list queue:", e));
+
+    const unlisten = listen<QueuedTask[]>(EVENTS.QUEUE_UPDATED, (e) =>
+      setTasks(e.payload),
+    );
+    return () => {
+      unlisten.then((u) => u());
+    };
+  }, []);
+
+  const waiting = tasks.filter((t) => t.status === "queued");
+  const ended = tasks
+    .filter((t) => t.status !== "queued" && t.status !== "running")
+    .slice(-MAX_ENDED)
+    .reverse();
+
+  const move = async (index: number, delta: number) => {
+    const ids = waiting.map((t) => t.id);
+    const target = index + delta;
+    if (target < 0 || target >= ids.length) return;
+    [ids[index], ids[target]] = [ids[target], ids[index]];
+    try {
+      setTasks(await commands.reorderQueue(ids));
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
+  const handleCancel = async (task: QueuedTask) => {
+    try {
+      await commands.cancelQueuedTask(task.id);
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
+  const handleStart = async () => {
+    try {
+      await commands.startQueue();
+    } catch (e) {
+      addToast(String(e), "error");
+    }
+  };
+
+  if (waiting.length === 0 && ended.length === 0) return null;
+
+  return (
+    <div className="mt-10 w-full max-w-lg animate-slide-up">
+      <div className="flex items-center mb-2">
+        <p className="flex-1 flex items-center gap-1.5 text-zinc-500 text-xs font-medium">
+          <ListOrdered className="w-3.5 h-3.5" />
+          Task queue
+        </p>
+        {waiting.length > 0 && (
+          <button
+            onClick={handleStart}
+            className="flex items-center gap-1 px-2 py-0.5 rounded-md text-[11px] text-zinc-400 hover:text-zinc-200 hover:bg-zinc-800 transition-colors"
+            title="Run queued tasks"
+          >
+            <Play className="w-3 h-3" />
+            Run queue
+          </button>
+        )}
+      </div>
+      <div className="flex flex-col gap-1">
+        {waiting.map((task, i) => (
+          <div
+            key={task.id}
+            className="flex items-center gap-2 px-3 py-2 bg-zinc-900/50 border border-zinc-800/50 rounded-lg"
+          >
+            <span className="text-[10px] text-zinc-600 shrink-0">{i + 1}</span>
+            <p className="flex-1 min-w-0 text-xs text-zinc-300 truncate" title={task.task}>
+              {task.task}
+            </p>
+            <button
+              onClick={() => move(i, -1)}
+              disabled={i === 0}
+              className="p-1 rounded-md text-zinc-500 hover:text-zinc-200 hover:bg-zinc-800 transition-colors disabled:opacity-30 disabled:pointer-events-none"
+              title="Move up"
+            >
+              <ArrowUp className="w-3.5 h-3.5" />
+            </button>
+            <button
+              onClick={() => move(i, 1)}
+              disabled={i === waiting.length - 1}
+              className="p-1 rounded-md text-zinc-500 hover:text-zinc-200 hover:bg-zinc-800 transition-colors disabled:opacity-30 disabled:pointer-events-none"
+              title="Move down"
+            >
+              <ArrowDown className="w-3.5 h-3.5" />
+            </button>
+            <button
+              onClick={() => handleCancel(task)}
+              className="p-1 rounded-md text-zinc-500 hover:text-red-400 hover:bg-zinc-800 transition-colors"
+              title="Remove from queue"
+            >
+              <X className="w-3.5 h-3.5" />
+            </button>
+          </div>
+        ))}
+        {ended.map((task) => (
+          <div
+            key={task.id}
+            className="flex items-center gap-2 px-3 py-2 border border-zinc-800/30 rounded-lg"
+          >
+            <p className="flex-1 min-w-0 text-xs text-zinc-500 truncate" title={task.task}>
+              {task.task}
+            </p>
+            <span
+              className={clsx(
+                "text-[10px] shrink-0",
+                task.status === "failed" ? "text-amber-400" : "text-zinc-500",
+              )}
+              title={task.error ?? task.result?.summary ?? undefined}
+            >
+              {outcome(task)}
+            </span>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
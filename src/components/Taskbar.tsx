@@ -1,6 +1,7 @@
 import { useAgentStore } from "../stores/agentStore";
 import { useUIStore } from "../stores/uiStore";
 import { ChevronUp } from "lucide-react";
+import { PauseButton } from "./PauseButton";
 import { StopButton } from "./StopButton";
 import { Spinner } from "./Spinner";
 
@@ -29,7 +30,7 @@ export function Taskbar() {
         </p>
         {thinking && (
           <p className="text-[10px] text-zinc-400 truncate leading-tight mt-0.5">
-            {thinking}
+            {thinking.length > 80 ? "…" + thinking.slice(-80) : thinking}
           </p>
         )}
       </div>
@@ -39,8 +40,9 @@ export function Taskbar() {
 
       <ChevronUp className="w-4 h-4 text-zinc-400 shrink-0" />
 
-      {/* Stop button */}
-      <div onClick={(e) => e.stopPropagation()}>
+      {/* Pause and stop buttons */}
+      <div className="flex items-center gap-1.5" onClick={(e) => e.stopPropagation()}>
+        <PauseButton />
         <StopButton />
       </div>
     </div>
@@ -1,9 +1,12 @@
 import { ChevronDown } from "lucide-react";
 import { useAgentStore } from "../stores/agentStore";
 import { useUIStore } from "../stores/uiStore";
+import { PauseButton } from "./PauseButton";
 import { StopButton } from "./StopButton";
 import { EstimatedTime } from "./EstimatedTime";
 import { ActionLog } from "./ActionLog";
+import { AgentMessageInput } from "./AgentMessageInput";
+import { ApprovalPrompt } from "./ApprovalPrompt";
 import { Spinner } from "./Spinner";
 import { expandTaskbar, collapseTaskbar } from "../lib/windowManager";
 import { useEffect } from "react";
@@ -12,6 +15,7 @@ export function TaskbarExpanded() {
   const task = useAgentStore((s) => s.task);
   const message = useAgentStore((s) => s.message);
   const thinking = useAgentStore((s) => s.thinking);
+  const usage = useAgentStore((s) => s.usage);
   const { setExpanded } = useUIStore();
 
   const truncatedTask = task.length > 45 ? task.slice(0, 45) + "…" : task;
@@ -42,6 +46,23 @@ export function TaskbarExpanded() {
           <EstimatedTime />
         </div>
 
+        {/* Token usage / cost */}
+        {usage && (
+          <p
+            className="text-[10px] text-zinc-500 shrink-0 tabular-nums"
+            title={`${usage.input_tokens} in / ${usage.output_tokens} out / ${usage.cache_read_input_tokens} cache read / ${usage.cache_creation_input_tokens} cache write`}
+          >
+            {formatTokens(
+              usage.input_tokens +
+                usage.output_tokens +
+                usage.cache_creation_input_tokens +
+                usage.cache_read_input_tokens,
+            )}{" "}
+            tok
+            {usage.cost_usd !== null && ` · $${usage.cost_usd.toFixed(3)}`}
+          </p>
+        )}
+
         {/* Collapse */}
         <button
           onClick={() => setExpanded(false)}
@@ -50,7 +71,8 @@ export function TaskbarExpanded() {
           <ChevronDown className="w-4 h-4" />
         </button>
 
-        {/* Stop */}
+        {/* Pause / stop */}
+        <PauseButton />
         <StopButton />
       </div>
 
@@ -58,10 +80,16 @@ export function TaskbarExpanded() {
       {thinking && (
         <div className="flex items-center gap-2 px-4 py-2 border-b border-white/5 bg-zinc-800/20">
           <Spinner size={12} className="text-blue-400 shrink-0" />
-          <p className="text-[11px] text-zinc-400 truncate">{thinking}</p>
+          {/* Streamed reasoning grows quickly; keep the latest part in view */}
+          <p className="text-[11px] text-zinc-400 line-clamp-3 break-words" title={thinking}>
+            {thinking.length > 300 ? "…" + thinking.slice(-300) : thinking}
+          </p>
         </div>
       )}
 
+      {/* Pending approval */}
+      <ApprovalPrompt />
+
       {/* Action log */}
       <div className="flex-1 overflow-hidden">
         <ActionLog />
@@ -75,6 +103,13 @@ export function TaskbarExpanded() {
           </p>
         </div>
       )}
+
+      {/* Steer the running agent */}
+      <AgentMessageInput />
     </div>
   );
 }
+
+function formatTokens(n: number): string {
+  return n >= 1000 ? `${(n / 1000).toFixed(1)}k` : `${n}`;
+}
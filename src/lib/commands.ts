@@ -1,13 +1,31 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Settings, CaptureResult, ShellResult, WindowInfo } from "./types";
+import type {
+  Settings,
+  CaptureResult,
+  ShellResult,
+  WindowInfo,
+  ProviderProfile,
+  ModelOption,
+  RunMeta,
+  RunRecord,
+  ReplayOptions,
+  QueuedTask,
+  Schedule,
+  NewSchedule,
+  MemoryEntry,
+  MemoryInput,
+} from "./types";
 
 export const commands = {
   listOllamaModels: (baseUrl: string, apiKey?: string) =>
-    invoke<{ id: string; label: string }[]>("list_ollama_models", {
+    invoke<ModelOption[]>("list_ollama_models", {
       baseUrl,
       apiKey: apiKey ?? null,
     }),
 
+  listProfileModels: (profile: ProviderProfile) =>
+    invoke<{ id: string; label: string }[]>("list_profile_models", { profile }),
+
   takeScreenshot: () => invoke<CaptureResult>("take_screenshot"),
 
   mouseMove: (x: number, y: number) => invoke("mouse_move", { x, y }),
@@ -35,7 +53,43 @@ export const commands = {
 
   startAgent: (task: string) => invoke("start_agent", { task }),
   stopAgent: () => invoke("stop_agent"),
+  pauseAgent: () => invoke("pause_agent"),
+  resumeAgent: () => invoke("resume_agent"),
+  sendAgentMessage: (text: string) => invoke("send_agent_message", { text }),
+  resumeRun: (id: string) => invoke("resume_run", { id }),
+  replayRun: (id: string, options?: Partial<ReplayOptions>) =>
+    invoke("replay_run", { id, options: options ?? null }),
+  listRuns: () => invoke<RunMeta[]>("list_runs"),
+  getRun: (id: string) => invoke<RunRecord>("get_run", { id }),
+  deleteRun: (id: string) => invoke("delete_run", { id }),
+  enqueueTask: (task: string) => invoke<QueuedTask>("enqueue_task", { task }),
+  listQueue: () => invoke<QueuedTask[]>("list_queue"),
+  reorderQueue: (ids: string[]) =>
+    invoke<QueuedTask[]>("reorder_queue", { ids }),
+  cancelQueuedTask: (id: string) => invoke("cancel_queued_task", { id }),
+  startQueue: () => invoke("start_queue"),
+  createSchedule: (schedule: NewSchedule) =>
+    invoke<Schedule>("create_schedule", { schedule }),
+  listSchedules: () => invoke<Schedule[]>("list_schedules"),
+  pauseSchedule: (id: string) => invoke<Schedule>("pause_schedule", { id }),
+  resumeSchedule: (id: string) => invoke<Schedule>("resume_schedule", { id }),
+  deleteSchedule: (id: string) => invoke("delete_schedule", { id }),
+  listMemories: () => invoke<MemoryEntry[]>("list_memories"),
+  searchMemories: (query: string, limit?: number) =>
+    invoke<MemoryEntry[]>("search_memories", { query, limit }),
+  saveMemory: (memory: MemoryInput) =>
+    invoke<MemoryEntry>("save_memory", { memory }),
+  deleteMemory: (id: string) => invoke("delete_memory", { id }),
+  approveAction: (id: string) => invoke("approve_action", { id }),
+  rejectAction: (id: string, reason?: string) =>
+    invoke("reject_action", { id, reason: reason || null }),
 
   getSettings: () => invoke<Settings>("get_settings"),
   saveSettings: (settings: Settings) => invoke("save_settings", { settings }),
+  /** Render the system prompt for `settings` (default: the saved ones). */
+  previewSystemPrompt: (settings?: Settings, provider?: string) =>
+    invoke<string>("preview_system_prompt", {
+      settings: settings ?? null,
+      provider: provider ?? null,
+    }),
 };
@@ -4,6 +4,25 @@ export const EVENTS = {
   ACTION_EXECUTED: "action-executed",
   AGENT_THINKING: "agent-thinking",
   AGENT_MESSAGE: "agent-message",
+  AGENT_MESSAGE_DELTA: "agent-message-delta",
+  AGENT_RETRYING: "agent-retrying",
+  AGENT_STREAM_RESET: "agent-stream-reset",
   CURSOR_MOVED: "cursor-moved",
   ESTIMATED_TIME: "estimated-time",
+  PROVIDER_SWITCHED: "provider-switched",
+  USAGE_UPDATED: "usage-updated",
+  BUDGET_EXCEEDED: "budget-exceeded",
+  LIMIT_REACHED: "limit-reached",
+  APPROVAL_REQUESTED: "approval-requested",
+  APPROVAL_RESOLVED: "approval-resolved",
+  STUCK_DETECTED: "stuck-detected",
+  TASK_FINISHED: "task-finished",
+  CONTEXT_COMPACTED: "context-compacted",
+  REPLAY_CHECKPOINT: "replay-checkpoint",
+  REPLAY_FINISHED: "replay-finished",
+  QUEUE_UPDATED: "queue-updated",
+  QUEUE_TASK_STARTED: "queue-task-started",
+  SCHEDULES_UPDATED: "schedules-updated",
+  SCHEDULE_FIRED: "schedule-fired",
+  MEMORIES_UPDATED: "memories-updated",
 } as const;
@@ -2,7 +2,230 @@ import { commands } from "./commands";
 
 export type UIMode = "start" | "taskbar";
 
-export type AgentStatus = "idle" | "running" | "paused" | "error";
+export type AgentStatus =
+  | "idle"
+  | "running"
+  | "paused"
+  | "error"
+  | "limit_reached";
+
+/** Action kinds that can be capped via `max_actions` or gated via `approval_policies`. */
+export const ACTION_KINDS = [
+  "bash_command",
+  "left_click",
+  "right_click",
+  "double_click",
+  "type",
+  "key",
+  "scroll",
+  "drag",
+  "text_editor_create",
+  "text_editor_replace",
+] as const;
+
+export type ApprovalPolicy = "auto" | "ask" | "deny";
+
+/** Payload of `approval-requested`; `details` is the serialized action. */
+export interface ApprovalRequest {
+  id: string;
+  action_type: string;
+  description: string;
+  details: Record<string, unknown>;
+  timeout_secs: number;
+}
+
+export type TaskStatus = "success" | "failure" | "blocked" | "unknown";
+
+/** Payload of `task-finished`, as reported by the model's `task_complete` call. */
+export interface TaskResult {
+  status: TaskStatus;
+  summary: string;
+  result?: Record<string, unknown>;
+}
+
+export type RunStatus =
+  | "running"
+  | "finished"
+  | "failed"
+  | "cancelled"
+  | "limit_reached"
+  | "stopped"
+  | "interrupted";
+
+/** A stored run, as listed by `list_runs`. */
+export interface RunMeta {
+  id: string;
+  task: string;
+  provider: string;
+  model: string;
+  status: RunStatus;
+  started_at: string;
+  updated_at: string;
+  ended_at: string | null;
+  result: TaskResult | null;
+  error: string | null;
+}
+
+/** A line of a run transcript; screenshots are referenced by file path. */
+export type TranscriptEntry =
+  | { type: "message"; timestamp: string; message: unknown }
+  | {
+      type: "action";
+      timestamp: string;
+      iteration: number;
+      kind: string;
+      description: string;
+    };
+
+export interface RunRecord {
+  meta: RunMeta;
+  settings: Partial<Settings>;
+  entries: TranscriptEntry[];
+}
+
+export type QueuedTaskStatus =
+  | "queued"
+  | "running"
+  | "finished"
+  | "failed"
+  | "cancelled";
+
+export interface QueuedTask {
+  id: string;
+  task: string;
+  status: QueuedTaskStatus;
+  enqueued_at: string;
+  started_at: string | null;
+  ended_at: string | null;
+  run_id: string | null;
+  result: TaskResult | null;
+  error: string | null;
+}
+
+export type ScheduleTiming =
+  /** Five-field cron expression in local time, e.g. "0 9 * * 1-5". */
+  | { type: "cron"; expression: string }
+  | { type: "once"; at: string };
+
+export type WhenBusy = "skip" | "queue";
+
+export type MissedRunPolicy = "skip" | "run_once";
+
+export interface Schedule {
+  id: string;
+  name: string;
+  task: string;
+  timing: ScheduleTiming;
+  when_busy: WhenBusy;
+  missed_run: MissedRunPolicy;
+  paused: boolean;
+  created_at: string;
+  next_run: string | null;
+  last_run: string | null;
+  /** `started`, `queued`, `skipped`, `missed` or `failed: <error>`. */
+  last_outcome: string | null;
+}
+
+export interface MemoryEntry {
+  id: string;
+  title: string;
+  content: string;
+  tags: string[];
+  created_at: string;
+  updated_at: string;
+  /** Run that last wrote the entry; null when written by the user. */
+  run_id: string | null;
+}
+
+/** Input for `save_memory`; without an `id` a new memory is created. */
+export interface MemoryInput {
+  id?: string;
+  title: string;
+  content: string;
+  tags?: string[];
+}
+
+export interface NewSchedule {
+  name?: string;
+  task: string;
+  timing: ScheduleTiming;
+  when_busy?: WhenBusy;
+  missed_run?: MissedRunPolicy;
+}
+
+/** Options for `replay_run`; omitted fields use the backend defaults. */
+export interface ReplayOptions {
+  pace_ms: number;
+  checkpoints: boolean;
+  /** Mean per-pixel difference (0-1) above which a checkpoint counts as diverged. */
+  diff_threshold: number;
+  on_divergence: "abort" | "hand_to_model";
+}
+
+/** Payload of `stuck-detected`. */
+export interface StuckDetection {
+  kind: "repeated_action" | "unchanged_screen";
+  count: number;
+  message: string;
+  escalated: boolean;
+}
+
+export interface LimitHit {
+  limit: "iterations" | "duration" | "actions" | "budget";
+  action?: string;
+  message: string;
+  screenshot: string | null;
+}
+
+export type AuthScheme =
+  | { type: "bearer" }
+  | { type: "header"; name: string }
+  | { type: "none" };
+
+export type TokenLimitField = "max_tokens" | "max_completion_tokens" | "omit";
+
+/** An OpenAI-compatible endpoint (LM Studio, vLLM, llama.cpp, LiteLLM, ...). */
+/** System prompt settings for one provider, replacing or extending the global ones. */
+export interface PromptOverride {
+  /** Replaces the global template when not empty. */
+  template: string;
+  /** Added after the global custom instructions. */
+  instructions: string;
+}
+
+export interface ProviderProfile {
+  id: string;
+  label: string;
+  base_url: string;
+  api_key: string;
+  auth: AuthScheme;
+  extra_headers: Record<string, string>;
+  token_limit_field: TokenLimitField;
+}
+
+/** Controls for Ollama's native `/api/chat` endpoint. */
+export interface OllamaOptions {
+  native_api: boolean;
+  num_ctx: number | null;
+  temperature: number | null;
+  num_predict: number | null;
+  keep_alive: string;
+  json_format: boolean;
+}
+
+/** A provider/model pair tried when the current one keeps failing. */
+export interface FallbackProvider {
+  provider: string;
+  model: string;
+}
+
+/** USD per million tokens. */
+export interface ModelPrice {
+  input: number;
+  output: number;
+  cache_write: number;
+  cache_read: number;
+}
 
 export interface Settings {
   anthropic_api_key: string;
@@ -10,11 +233,48 @@ export interface Settings {
   openrouter_api_key: string;
   ollama_api_key: string;
   ollama_base_url: string;
+  ollama_options: OllamaOptions;
+  gemini_api_key: string;
+  gemini_base_url: string;
+  custom_providers: ProviderProfile[];
   provider: string;
   model: string;
+  fallback_providers: FallbackProvider[];
   display_width: number;
   display_height: number;
   shell_timeout_secs: number;
+  thinking_budget_tokens: number;
+  reasoning_effort: "" | "low" | "medium" | "high";
+  retry_max_attempts: number;
+  retry_max_delay_secs: number;
+  model_prices: Record<string, ModelPrice>;
+  max_tokens_per_task: number;
+  max_cost_per_task_usd: number;
+  max_iterations: number;
+  max_run_duration_secs: number;
+  max_actions: Record<string, number>;
+  approval_policies: Record<string, ApprovalPolicy>;
+  approval_timeout_secs: number;
+  stuck_repeat_threshold: number;
+  stuck_unchanged_threshold: number;
+  stuck_max_nudges: number;
+  completion_reminders: number;
+  /** Estimated tokens above which older turns are summarized; 0 disables. */
+  compaction_threshold_tokens: number;
+  compaction_keep_recent: number;
+  /** Screenshots kept at full size, then as grayscale thumbnails; older become text. */
+  screenshot_history_full: number;
+  screenshot_history_thumbnails: number;
+  screenshot_thumbnail_width: number;
+  /** Memory tools, and how many relevant memories go into a new task's message. */
+  memory_enabled: boolean;
+  memory_inject_count: number;
+  /** Template with {{os}}, {{date}}, {{screen_size}}, {{username}}, {{shell}} and
+   * {{custom_instructions}}; empty uses the built-in prompt. */
+  system_prompt_template: string;
+  custom_instructions: string;
+  /** Keyed by built-in provider id or custom profile id. */
+  provider_prompts: Record<string, PromptOverride>;
   setup_complete: boolean;
 }
 
@@ -27,6 +287,15 @@ export interface ActionEntry {
   output?: string;
 }
 
+export interface RunUsage {
+  input_tokens: number;
+  output_tokens: number;
+  cache_creation_input_tokens: number;
+  cache_read_input_tokens: number;
+  requests: number;
+  cost_usd: number | null;
+}
+
 export interface CaptureResult {
   base64: string;
   orig_width: number;
@@ -47,14 +316,24 @@ export interface WindowInfo {
   title: string;
 }
 
+/** Capability fields are only reported for Ollama models; `null` means unknown. */
+export interface ModelOption {
+  id: string;
+  label: string;
+  context_length?: number | null;
+  vision?: boolean | null;
+  tool_calling?: boolean | null;
+}
+
 export interface ModelFetchResult {
-  models: { id: string; label: string }[];
+  models: ModelOption[];
   hadError: boolean;
 }
 
 export const PROVIDERS = [
   { id: "anthropic", label: "Anthropic" },
   { id: "openai", label: "OpenAI" },
+  { id: "gemini", label: "Google Gemini" },
   { id: "openrouter", label: "OpenRouter" },
   { id: "ollama", label: "Ollama" },
 ] as const;
@@ -75,6 +354,11 @@ export const MODELS: Record<string, { id: string; label: string }[]> = {
     { id: "o3", label: "o3" },
     { id: "o4-mini", label: "o4-mini" },
   ],
+  gemini: [
+    { id: "gemini-2.5-pro", label: "Gemini 2.5 Pro" },
+    { id: "gemini-2.5-flash", label: "Gemini 2.5 Flash" },
+    { id: "gemini-2.5-flash-lite", label: "Gemini 2.5 Flash-Lite" },
+  ],
   openrouter: [
     { id: "anthropic/claude-sonnet-4.6", label: "Claude Sonnet 4.6" },
     { id: "anthropic/claude-opus-4.6", label: "Claude Opus 4.6" },
@@ -104,17 +388,23 @@ export async function fetchModels(
 export async function fetchModelsDetailed(
   provider: string,
   apiKey: string,
-  ollamaBaseUrl?: string
+  ollamaBaseUrl?: string,
+  customProviders: ProviderProfile[] = []
 ): Promise<ModelFetchResult> {
-  if (!apiKey && provider !== "ollama") {
+  const custom = customProviders.find((p) => p.id === provider);
+  if (!apiKey && provider !== "ollama" && !custom) {
     return { models: [], hadError: true };
   }
 
   try {
-    if (provider === "anthropic") {
+    if (custom) {
+      return { models: await commands.listProfileModels(custom), hadError: false };
+    } else if (provider === "anthropic") {
       return { models: await fetchAnthropicModels(apiKey), hadError: false };
     } else if (provider === "openai") {
       return { models: await fetchOpenAIModels(apiKey), hadError: false };
+    } else if (provider === "gemini") {
+      return { models: await fetchGeminiModels(apiKey), hadError: false };
     } else if (provider === "openrouter") {
       return { models: await fetchOpenRouterModels(apiKey), hadError: false };
     } else if (provider === "ollama") {
@@ -172,6 +462,26 @@ async function fetchOpenAIModels(
   return models;
 }
 
+async function fetchGeminiModels(
+  apiKey: string
+): Promise<{ id: string; label: string }[]> {
+  const res = await fetch(
+    "https://generativelanguage.googleapis.com/v1beta/models?pageSize=1000",
+    { headers: { "x-goog-api-key": apiKey } }
+  );
+  if (!res.ok) throw new Error(`Gemini model fetch failed: ${res.status}`);
+  const data = await res.json();
+  const models = (data.models ?? [])
+    .filter((m: { supportedGenerationMethods?: string[] }) =>
+      (m.supportedGenerationMethods ?? []).includes("generateContent")
+    )
+    .map((m: { name: string; displayName?: string }) => {
+      const id = m.name.replace(/^models\//, "");
+      return { id, label: m.displayName ?? id };
+    });
+  return models;
+}
+
 async function fetchOpenRouterModels(
   apiKey: string
 ): Promise<{ id: string; label: string }[]> {
@@ -195,7 +505,7 @@ async function fetchOpenRouterModels(
 async function fetchOllamaModels(
   baseUrl?: string,
   apiKey?: string
-): Promise<{ id: string; label: string }[]> {
+): Promise<ModelOption[]> {
   return commands.listOllamaModels(
     normalizeOllamaBaseUrl(baseUrl),
     apiKey?.trim() || undefined,
@@ -1,13 +1,22 @@
 import { create } from "zustand";
-import type { ActionEntry, AgentStatus } from "../lib/types";
+import type {
+  ActionEntry,
+  AgentStatus,
+  ApprovalRequest,
+  RunUsage,
+} from "../lib/types";
 
 interface AgentState {
   status: AgentStatus;
   task: string;
   actions: ActionEntry[];
   thinking: string;
+  thinkingStreaming: boolean;
   message: string;
+  messageStreaming: boolean;
   estimatedSeconds: number | null;
+  usage: RunUsage | null;
+  pendingApproval: ApprovalRequest | null;
   screenshot: string | null;
   cursorX: number;
   cursorY: number;
@@ -16,8 +25,14 @@ interface AgentState {
   setTask: (task: string) => void;
   addAction: (action: ActionEntry) => void;
   setThinking: (text: string) => void;
+  appendThinkingDelta: (delta: string) => void;
   setMessage: (text: string) => void;
+  appendMessageDelta: (delta: string) => void;
+  endMessageStream: () => void;
+  discardMessageStream: () => void;
   setEstimatedSeconds: (seconds: number | null) => void;
+  setUsage: (usage: RunUsage | null) => void;
+  setPendingApproval: (request: ApprovalRequest | null) => void;
   setScreenshot: (base64: string | null) => void;
   setCursor: (x: number, y: number) => void;
   reset: () => void;
@@ -28,8 +43,12 @@ export const useAgentStore = create<AgentState>((set) => ({
   task: "",
   actions: [],
   thinking: "",
+  thinkingStreaming: false,
   message: "",
+  messageStreaming: false,
   estimatedSeconds: null,
+  usage: null,
+  pendingApproval: null,
   screenshot: null,
   cursorX: 0,
   cursorY: 0,
@@ -38,9 +57,24 @@ export const useAgentStore = create<AgentState>((set) => ({
   setTask: (task) => set({ task }),
   addAction: (action) =>
     set((s) => ({ actions: [...s.actions, action] })),
-  setThinking: (thinking) => set({ thinking }),
-  setMessage: (message) => set({ message }),
+  setThinking: (thinking) => set({ thinking, thinkingStreaming: false }),
+  appendThinkingDelta: (delta) =>
+    set((s) => ({
+      thinking: s.thinkingStreaming ? s.thinking + delta : delta,
+      thinkingStreaming: true,
+    })),
+  setMessage: (message) => set({ message, messageStreaming: false }),
+  appendMessageDelta: (delta) =>
+    set((s) => ({
+      message: s.messageStreaming ? s.message + delta : delta,
+      messageStreaming: true,
+    })),
+  endMessageStream: () => set({ messageStreaming: false }),
+  discardMessageStream: () =>
+    set((s) => (s.messageStreaming ? { message: "", messageStreaming: false } : {})),
   setEstimatedSeconds: (estimatedSeconds) => set({ estimatedSeconds }),
+  setUsage: (usage) => set({ usage }),
+  setPendingApproval: (pendingApproval) => set({ pendingApproval }),
   setScreenshot: (screenshot) => set({ screenshot }),
   setCursor: (cursorX, cursorY) => set({ cursorX, cursorY }),
   reset: () =>
@@ -49,8 +83,12 @@ export const useAgentStore = create<AgentState>((set) => ({
       task: "",
       actions: [],
       thinking: "",
+      thinkingStreaming: false,
       message: "",
+      messageStreaming: false,
       estimatedSeconds: null,
+      usage: null,
+      pendingApproval: null,
       screenshot: null,
       cursorX: 0,
       cursorY: 0,
@@ -20,11 +20,53 @@ const defaultSettings: Settings = {
   openrouter_api_key: "",
   ollama_api_key: "",
   ollama_base_url: "http://127.0.0.1:11434",
+  ollama_options: {
+    native_api: false,
+    num_ctx: null,
+    temperature: null,
+    num_predict: null,
+    keep_alive: "",
+    json_format: false,
+  },
+  gemini_api_key: "",
+  gemini_base_url: "https://generativelanguage.googleapis.com/v1beta",
+  custom_providers: [],
   provider: "anthropic",
   model: "claude-sonnet-4-6",
+  fallback_providers: [],
   display_width: 1280,
   display_height: 800,
   shell_timeout_secs: 30,
+  thinking_budget_tokens: 0,
+  reasoning_effort: "",
+  retry_max_attempts: 4,
+  retry_max_delay_secs: 60,
+  model_prices: {},
+  max_tokens_per_task: 0,
+  max_cost_per_task_usd: 0,
+  max_iterations: 100,
+  max_run_duration_secs: 1800,
+  max_actions: {},
+  approval_policies: {
+    bash_command: "ask",
+    text_editor_create: "ask",
+    text_editor_replace: "ask",
+  },
+  approval_timeout_secs: 120,
+  stuck_repeat_threshold: 3,
+  stuck_unchanged_threshold: 5,
+  stuck_max_nudges: 2,
+  completion_reminders: 2,
+  compaction_threshold_tokens: 100000,
+  compaction_keep_recent: 6,
+  screenshot_history_full: 5,
+  screenshot_history_thumbnails: 5,
+  screenshot_thumbnail_width: 320,
+  memory_enabled: true,
+  memory_inject_count: 3,
+  system_prompt_template: "",
+  custom_instructions: "",
+  provider_prompts: {},
   setup_complete: false,
 };
 
@@ -46,6 +88,7 @@ export const useSettingsStore = create<SettingsState>((set, get) => ({
           (merged.anthropic_api_key ||
             merged.openai_api_key ||
             merged.openrouter_api_key ||
+            merged.gemini_api_key ||
             merged.ollama_api_key)
         ) {
           merged.setup_complete = true;